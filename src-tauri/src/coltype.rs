@@ -0,0 +1,371 @@
+//! 列类型解析
+//!
+//! 把 `TableColumn.column_type` 解析为结构化的 `ColumnType`，差异对比、规范检查和代码生成
+//! 按基本类型和参数判断，不需要匹配 `int(11)`、`decimal(10,2) unsigned zerofill` 这样的原文。
+//! column_type 的格式：MySQL 为 COLUMNS.COLUMN_TYPE，PostgreSQL 为 `format_type` 的结果，
+//! SQLite 为建表语句中声明的类型。
+
+use serde::{Deserialize, Serialize};
+
+use crate::Driver;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnType {
+    /// 小写的基本类型，不包含参数和修饰，如 `varchar`、`character varying`、`timestamp`。
+    /// 带引号的类型名保持原样，如 PostgreSQL 的 `"char"`
+    pub base: String,
+    /// 字符串、二进制和位串类型的长度，整数类型为 MySQL 的显示宽度
+    pub length: Option<u32>,
+    /// 数值类型的精度，时间类型的小数秒精度
+    pub precision: Option<u32>,
+    pub scale: Option<u32>,
+    pub unsigned: bool,
+    pub zerofill: bool,
+    /// ENUM 和 SET 的成员
+    pub members: Vec<String>,
+    /// 数组的维数，不是数组时为 0
+    pub array_dims: u32,
+    /// 带时区的时间类型：PostgreSQL 的 `with time zone`，
+    /// MySQL 的 TIMESTAMP（按会话时区转换为 UTC 存储）
+    pub with_time_zone: bool,
+}
+
+/// 解析列类型，无法识别的部分忽略
+pub fn parse(column_type: &str, driver: Driver) -> ColumnType {
+    let mut ty = ColumnType::default();
+    let mut rest = column_type.trim();
+    // PostgreSQL 的 integer[]、text[][]，声明的维数不会强制检查
+    while let Some(open) = rest.strip_suffix(']').and_then(|r| r.rfind('[')) {
+        ty.array_dims += 1;
+        rest = rest[..open].trim_end();
+    }
+
+    let (words, args) = tokens(rest);
+    let mut base = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        match words[i].as_str() {
+            "unsigned" => ty.unsigned = true,
+            "signed" => {}
+            "zerofill" => ty.zerofill = true,
+            w @ ("with" | "without") if matches!(words.get(i + 1..i + 3), Some([t, z]) if t == "time" && z == "zone") =>
+            {
+                ty.with_time_zone = w == "with";
+                i += 2;
+            }
+            w => base.push(w),
+        }
+        i += 1;
+    }
+    ty.base = match base.join(" ").as_str() {
+        "timestamptz" => "timestamp".to_string(),
+        "timetz" => "time".to_string(),
+        base => base.to_string(),
+    };
+    if matches!(base.as_slice(), ["timestamptz" | "timetz"])
+        || (driver == Driver::Mysql && ty.base == "timestamp")
+    {
+        ty.with_time_zone = true;
+    }
+
+    let Some(args) = args else {
+        return ty;
+    };
+    if matches!(ty.base.as_str(), "enum" | "set") {
+        ty.members = members(&args);
+        return ty;
+    }
+    let nums = args
+        .split(',')
+        .map(|a| a.trim().parse::<u32>().ok())
+        .collect::<Vec<_>>();
+    match nums.as_slice() {
+        [precision, scale] => {
+            ty.precision = *precision;
+            ty.scale = *scale;
+        }
+        [n] if is_temporal(&ty.base) || is_decimal(&ty.base) => ty.precision = *n,
+        [n] => ty.length = *n,
+        _ => {}
+    }
+    ty
+}
+
+/// 代码生成使用的 Rust 类型，按 sqlx 的类型对应关系，可以为 NULL 时为 `Option<T>`。
+/// 数组按维数嵌套 `Vec`，无法识别的类型为 `String`
+pub fn rust_type(ty: &ColumnType, driver: Driver, nullable: bool) -> String {
+    let mut res = match driver {
+        Driver::Mysql => mysql_rust_type(ty),
+        Driver::Postgres => pg_rust_type(ty),
+        Driver::Sqlite => sqlite_rust_type(ty),
+    }
+    .to_string();
+    for _ in 0..ty.array_dims {
+        res = format!("Vec<{res}>");
+    }
+    if nullable {
+        res = format!("Option<{res}>");
+    }
+    res
+}
+
+/// MySQL 8.0.19 起整数类型不再显示宽度，但 TINYINT(1) 除外，仍按 BOOLEAN 处理。
+/// YEAR 在 sqlx 中为 u16
+fn mysql_rust_type(ty: &ColumnType) -> &'static str {
+    match (ty.base.as_str(), ty.unsigned) {
+        ("tinyint", false) if ty.length == Some(1) => "bool",
+        ("bool" | "boolean", _) => "bool",
+        ("tinyint", false) => "i8",
+        ("tinyint", true) => "u8",
+        ("smallint", false) => "i16",
+        ("smallint", true) | ("year", _) => "u16",
+        ("mediumint" | "int" | "integer", false) => "i32",
+        ("mediumint" | "int" | "integer", true) => "u32",
+        ("bigint", false) => "i64",
+        ("bigint", true) | ("bit", _) => "u64",
+        ("float", _) => "f32",
+        ("double" | "double precision" | "real", _) => "f64",
+        ("decimal" | "numeric" | "dec" | "fixed", _) => "bigdecimal::BigDecimal",
+        ("date", _) => "time::Date",
+        ("time", _) => "time::Time",
+        ("datetime", _) => "time::PrimitiveDateTime",
+        ("timestamp", _) => "time::OffsetDateTime",
+        ("binary" | "varbinary" | "tinyblob" | "blob" | "mediumblob" | "longblob", _) => "Vec<u8>",
+        ("json", _) => "serde_json::Value",
+        _ => "String",
+    }
+}
+
+fn pg_rust_type(ty: &ColumnType) -> &'static str {
+    match ty.base.as_str() {
+        "boolean" | "bool" => "bool",
+        "\"char\"" => "i8",
+        "smallint" | "int2" | "smallserial" => "i16",
+        "integer" | "int" | "int4" | "serial" => "i32",
+        "bigint" | "int8" | "bigserial" => "i64",
+        "oid" => "sqlx::postgres::types::Oid",
+        "real" | "float4" => "f32",
+        "double precision" | "float8" => "f64",
+        "numeric" | "decimal" => "bigdecimal::BigDecimal",
+        "money" => "sqlx::postgres::types::PgMoney",
+        "bytea" => "Vec<u8>",
+        "date" => "time::Date",
+        "time" if ty.with_time_zone => "sqlx::postgres::types::PgTimeTz",
+        "time" => "time::Time",
+        "timestamp" if ty.with_time_zone => "time::OffsetDateTime",
+        "timestamp" => "time::PrimitiveDateTime",
+        b if b.starts_with("interval") => "sqlx::postgres::types::PgInterval",
+        "uuid" => "uuid::Uuid",
+        "json" | "jsonb" => "serde_json::Value",
+        "inet" | "cidr" => "ipnetwork::IpNetwork",
+        "macaddr" => "mac_address::MacAddress",
+        "bit" | "bit varying" | "varbit" => "bit_vec::BitVec",
+        _ => "String",
+    }
+}
+
+/// 先按 sqlx 支持的类型名，再按 SQLite 的类型亲和性规则
+fn sqlite_rust_type(ty: &ColumnType) -> &'static str {
+    let base = ty.base.as_str();
+    match base {
+        "boolean" | "bool" => "bool",
+        "date" => "time::Date",
+        "time" => "time::Time",
+        "datetime" | "timestamp" => "time::PrimitiveDateTime",
+        _ if base.contains("int") => "i64",
+        _ if ["char", "clob", "text"].iter().any(|t| base.contains(t)) => "String",
+        _ if base.is_empty() || base.contains("blob") => "Vec<u8>",
+        _ => "f64",
+    }
+}
+
+/// 类型中的单词（不在引号中的转为小写）和第一个括号中的参数
+fn tokens(s: &str) -> (Vec<String>, Option<String>) {
+    let mut words = Vec::new();
+    let mut args = None;
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '(' => {
+                let mut depth = 1;
+                let mut quote = None;
+                let mut end = s.len();
+                for (j, c) in chars.by_ref() {
+                    match (quote, c) {
+                        (Some(q), c) if c == q => quote = None,
+                        (Some(_), _) => {}
+                        (None, '\'' | '"') => quote = Some(c),
+                        (None, '(') => depth += 1,
+                        (None, ')') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                end = j;
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                args.get_or_insert_with(|| s[i + 1..end].to_string());
+            }
+            '"' => {
+                let end = s[i + 1..].find('"').map_or(s.len(), |j| i + j + 2);
+                words.push(s[i..end].to_string());
+                while chars.next_if(|&(j, _)| j < end).is_some() {}
+            }
+            c if c.is_whitespace() || c == ',' => {}
+            _ => {
+                let mut end = s.len();
+                while let Some(&(j, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == '"' {
+                        end = j;
+                        break;
+                    }
+                    chars.next();
+                }
+                words.push(s[i..end].to_lowercase());
+            }
+        }
+    }
+    (words, args)
+}
+
+/// ENUM、SET 的成员，`''` 和 `\'` 为引号
+fn members(args: &str) -> Vec<String> {
+    let mut res = Vec::new();
+    let mut chars = args.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\'' {
+            continue;
+        }
+        let mut member = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => member.extend(chars.next()),
+                '\'' if chars.next_if_eq(&'\'').is_some() => member.push('\''),
+                '\'' => break,
+                c => member.push(c),
+            }
+        }
+        res.push(member);
+    }
+    res
+}
+
+/// 括号中为小数秒精度的类型
+fn is_temporal(base: &str) -> bool {
+    ["time", "datetime", "interval"]
+        .iter()
+        .any(|t| base.starts_with(t))
+}
+
+/// 括号中为精度的数值类型
+fn is_decimal(base: &str) -> bool {
+    matches!(
+        base,
+        "decimal" | "numeric" | "dec" | "fixed" | "float" | "double" | "double precision" | "real"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mysql() {
+        let ty = parse("int(11)", Driver::Mysql);
+        assert_eq!((ty.base.as_str(), ty.length), ("int", Some(11)));
+
+        let ty = parse("decimal(10,2) unsigned zerofill", Driver::Mysql);
+        assert_eq!(ty.base, "decimal");
+        assert_eq!((ty.precision, ty.scale), (Some(10), Some(2)));
+        assert!(ty.unsigned && ty.zerofill);
+
+        let ty = parse("enum('a','it''s','b,c')", Driver::Mysql);
+        assert_eq!(ty.members, ["a", "it's", "b,c"]);
+
+        let ty = parse("datetime(6)", Driver::Mysql);
+        assert_eq!((ty.precision, ty.with_time_zone), (Some(6), false));
+        assert!(parse("timestamp", Driver::Mysql).with_time_zone);
+    }
+
+    #[test]
+    fn parse_postgres() {
+        let ty = parse("character varying(255)[]", Driver::Postgres);
+        assert_eq!(ty.base, "character varying");
+        assert_eq!((ty.length, ty.array_dims), (Some(255), 1));
+
+        let ty = parse("timestamp(3) with time zone", Driver::Postgres);
+        assert_eq!(ty.base, "timestamp");
+        assert_eq!((ty.precision, ty.with_time_zone), (Some(3), true));
+        assert!(!parse("time without time zone", Driver::Postgres).with_time_zone);
+        assert!(parse("timestamptz", Driver::Postgres).with_time_zone);
+
+        assert_eq!(parse("\"char\"", Driver::Postgres).base, "\"char\"");
+        assert_eq!(parse("numeric(12,4)", Driver::Postgres).scale, Some(4));
+        let ty = parse("interval day to second(3)", Driver::Postgres);
+        assert_eq!(
+            (ty.base.as_str(), ty.precision),
+            ("interval day to second", Some(3))
+        );
+    }
+
+    #[test]
+    fn rust_types() {
+        let rust = |column_type: &str, driver, nullable| {
+            rust_type(&parse(column_type, driver), driver, nullable)
+        };
+        assert_eq!(rust("tinyint(1)", Driver::Mysql, false), "bool");
+        assert_eq!(rust("tinyint", Driver::Mysql, false), "i8");
+        assert_eq!(rust("tinyint(1) unsigned", Driver::Mysql, false), "u8");
+        assert_eq!(rust("int unsigned", Driver::Mysql, false), "u32");
+        assert_eq!(rust("bigint(20) unsigned", Driver::Mysql, false), "u64");
+        assert_eq!(rust("year", Driver::Mysql, false), "u16");
+        assert_eq!(rust("date", Driver::Mysql, false), "time::Date");
+        assert_eq!(
+            rust("decimal(10,2) unsigned", Driver::Mysql, false),
+            "bigdecimal::BigDecimal"
+        );
+        assert_eq!(
+            rust("timestamp", Driver::Mysql, true),
+            "Option<time::OffsetDateTime>"
+        );
+
+        assert_eq!(
+            rust("numeric(12,4)", Driver::Postgres, false),
+            "bigdecimal::BigDecimal"
+        );
+        assert_eq!(rust("integer[]", Driver::Postgres, false), "Vec<i32>");
+        assert_eq!(
+            rust("character varying(20)[]", Driver::Postgres, true),
+            "Option<Vec<String>>"
+        );
+        assert_eq!(
+            rust("text[][]", Driver::Postgres, false),
+            "Vec<Vec<String>>"
+        );
+        assert_eq!(
+            rust("timestamp(3) with time zone", Driver::Postgres, false),
+            "time::OffsetDateTime"
+        );
+        assert_eq!(
+            rust("timestamp without time zone", Driver::Postgres, false),
+            "time::PrimitiveDateTime"
+        );
+
+        assert_eq!(rust("INTEGER", Driver::Sqlite, false), "i64");
+        assert_eq!(rust("VARCHAR(20)", Driver::Sqlite, true), "Option<String>");
+        assert_eq!(rust("DECIMAL(10, 2)", Driver::Sqlite, false), "f64");
+        assert_eq!(rust("", Driver::Sqlite, false), "Vec<u8>");
+    }
+
+    #[test]
+    fn parse_sqlite() {
+        let ty = parse("VARCHAR(20)", Driver::Sqlite);
+        assert_eq!((ty.base.as_str(), ty.length), ("varchar", Some(20)));
+        let ty = parse("DECIMAL(10, 2)", Driver::Sqlite);
+        assert_eq!((ty.precision, ty.scale), (Some(10), Some(2)));
+        assert!(!parse("TIMESTAMP", Driver::Sqlite).with_time_zone);
+        assert_eq!(parse("", Driver::Sqlite), ColumnType::default());
+    }
+}
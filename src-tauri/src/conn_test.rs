@@ -0,0 +1,165 @@
+//! 测试数据库连接
+//!
+//! 使用单个连接而不是连接池，返回服务端信息，失败原因见 `ErrorCode`。
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sqlx::{Connection, MySqlConnection, PgConnection, Row, SqliteConnection};
+
+use crate::{
+    error::{Error, ErrorCode},
+    options::SslMode,
+    tunnel, DResult, DbConn, Driver,
+};
+
+/// 连接超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 连接测试结果
+#[derive(Debug, Serialize)]
+pub struct ConnTest {
+    /// 数据库产品，MySQL、MariaDB、PostgreSQL、SQLite
+    product: String,
+    /// 服务端版本
+    version: String,
+    /// 建立连接耗时（毫秒）
+    connect_ms: u128,
+    /// 一次往返耗时（毫秒）
+    latency_ms: u128,
+    /// 当前数据库
+    database: Option<String>,
+    /// 当前模式，仅 PostgreSQL
+    schema: Option<String>,
+    /// TLS 状态，SQLite 为空
+    tls: Option<TlsStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TlsStatus {
+    /// 配置的 TLS 模式
+    mode: SslMode,
+    /// 实际连接是否加密
+    encrypted: bool,
+    /// TLS 协议版本，如 TLSv1.3
+    version: Option<String>,
+    cipher: Option<String>,
+}
+
+async fn timed<T, F>(f: F) -> DResult<(T, u128)>
+where
+    F: std::future::Future<Output = Result<T, sqlx::Error>>,
+{
+    let start = Instant::now();
+    let res = tokio::time::timeout(CONNECT_TIMEOUT, f)
+        .await
+        .map_err(|_| Error::new(ErrorCode::Timeout, "connect timed out"))??;
+    Ok((res, start.elapsed().as_millis()))
+}
+
+pub async fn test(conn: &DbConn) -> DResult<ConnTest> {
+    let (local, _tunnel) = tunnel::through(conn).await?;
+    let conn = &local;
+    match conn.driver {
+        Driver::Mysql => {
+            let (mut c, connect_ms) =
+                timed(MySqlConnection::connect_with(&conn.mysql_options()?)).await?;
+            let ((), latency_ms) = timed(c.ping()).await?;
+            let row = sqlx::query("SELECT VERSION(), DATABASE()")
+                .fetch_one(&mut c)
+                .await?;
+            let version: String = row.try_get(0)?;
+            let database: Option<String> = row.try_get(1)?;
+            let status: Vec<(String, String)> = sqlx::query_as(
+                "SHOW SESSION STATUS WHERE Variable_name IN ('Ssl_version', 'Ssl_cipher')",
+            )
+            .fetch_all(&mut c)
+            .await?;
+            let _ = c.close().await;
+
+            let status = |name: &str| {
+                status
+                    .iter()
+                    .find(|(k, _)| k == name)
+                    .map(|(_, v)| v.clone())
+                    .filter(|v| !v.is_empty())
+            };
+            let tls = TlsStatus {
+                mode: conn.tls.mode,
+                encrypted: status("Ssl_cipher").is_some(),
+                version: status("Ssl_version"),
+                cipher: status("Ssl_cipher"),
+            };
+
+            let product = if version.contains("MariaDB") {
+                "MariaDB"
+            } else {
+                "MySQL"
+            };
+            Ok(ConnTest {
+                product: product.to_string(),
+                version,
+                connect_ms,
+                latency_ms,
+                database,
+                schema: None,
+                tls: Some(tls),
+            })
+        }
+        Driver::Postgres => {
+            let (mut c, connect_ms) =
+                timed(PgConnection::connect_with(&conn.pg_options()?)).await?;
+            let ((), latency_ms) = timed(c.ping()).await?;
+            let row = sqlx::query(
+                "SELECT current_setting('server_version'), current_database(), current_schema()",
+            )
+            .fetch_one(&mut c)
+            .await?;
+            let version: String = row.try_get(0)?;
+            let database: Option<String> = row.try_get(1)?;
+            let schema: Option<String> = row.try_get(2)?;
+            let ssl: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+                "SELECT version, cipher FROM pg_stat_ssl WHERE pid = pg_backend_pid() AND ssl",
+            )
+            .fetch_optional(&mut c)
+            .await?;
+            let _ = c.close().await;
+
+            let tls = TlsStatus {
+                mode: conn.tls.mode,
+                encrypted: ssl.is_some(),
+                version: ssl.as_ref().and_then(|s| s.0.clone()),
+                cipher: ssl.and_then(|s| s.1),
+            };
+
+            Ok(ConnTest {
+                product: "PostgreSQL".to_string(),
+                version,
+                connect_ms,
+                latency_ms,
+                database,
+                schema,
+                tls: Some(tls),
+            })
+        }
+        Driver::Sqlite => {
+            let (mut c, connect_ms) =
+                timed(SqliteConnection::connect_with(&conn.sqlite_options()?)).await?;
+            let ((), latency_ms) = timed(c.ping()).await?;
+            let version: String = sqlx::query_scalar("SELECT sqlite_version()")
+                .fetch_one(&mut c)
+                .await?;
+            let _ = c.close().await;
+
+            Ok(ConnTest {
+                product: "SQLite".to_string(),
+                version,
+                connect_ms,
+                latency_ms,
+                database: Some(conn.database.clone()),
+                schema: Some("main".to_string()),
+                tls: None,
+            })
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! 错误类型
+//!
+//! 所有命令都返回 `Error`，界面根据 `code` 区分错误原因，
+//! `message` 保留驱动或系统返回的原始信息。
+
+use std::fmt;
+
+use serde::Serialize;
+use sqlx::mysql::MySqlDatabaseError;
+
+/// 错误码，序列化后的值不随版本变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// 域名解析失败
+    Dns,
+    /// 连接被拒绝
+    Refused,
+    /// 连接或获取连接超时
+    Timeout,
+    /// 用户名或密码错误、没有权限
+    Auth,
+    /// 数据库不存在
+    UnknownDatabase,
+    /// TLS 握手或证书错误
+    Tls,
+    /// 连接配置错误，如证书文件不存在
+    Config,
+    /// SSH 隧道连接或认证失败
+    Ssh,
+    /// 连接不存在
+    NotFound,
+    /// 受保护的连接执行写操作未确认
+    Protected,
+    /// SQL 执行失败
+    Query,
+    /// 查询结果无法解析
+    Decode,
+    /// 读写本地文件失败
+    Io,
+    /// 本地保存的连接或密钥损坏
+    Store,
+    /// 钥匙串不可用，保存的密码需要输入主密码解锁
+    VaultLocked,
+    /// 导入的文件格式错误
+    Import,
+    /// 当前数据库不支持该操作
+    Unsupported,
+    Other,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Error {
+    pub code: ErrorCode,
+    /// 原始错误信息
+    pub message: String,
+    /// 数据库返回的 SQLSTATE
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sqlstate: Option<String>,
+    /// 出错的 SQL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql: Option<String>,
+    /// 出错的对象，如表名、文件路径
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object: Option<String>,
+}
+
+impl Error {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            sqlstate: None,
+            sql: None,
+            object: None,
+        }
+    }
+
+    pub fn with_sql(mut self, sql: impl Into<String>) -> Self {
+        self.sql = Some(sql.into());
+        self
+    }
+
+    pub fn with_object(mut self, object: impl Into<String>) -> Self {
+        self.object = Some(object.into());
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?}] {}", self.code, self.message)?;
+        if let Some(object) = &self.object {
+            write!(f, " ({object})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<sqlx::Error> for Error {
+    fn from(e: sqlx::Error) -> Self {
+        let sqlstate = match &e {
+            sqlx::Error::Database(db) => db.code().map(|c| c.into_owned()),
+            _ => None,
+        };
+        Self {
+            sqlstate,
+            ..Self::new(classify(&e), e.to_string())
+        }
+    }
+}
+
+/// 根据驱动错误判断错误码
+fn classify(e: &sqlx::Error) -> ErrorCode {
+    match e {
+        sqlx::Error::Io(io) => match io.kind() {
+            std::io::ErrorKind::ConnectionRefused => ErrorCode::Refused,
+            std::io::ErrorKind::TimedOut => ErrorCode::Timeout,
+            _ => {
+                let msg = io.to_string();
+                if msg.contains("lookup address")
+                    || msg.contains("Name or service not known")
+                    || msg.contains("nodename nor servname")
+                    || msg.contains("No such host")
+                {
+                    ErrorCode::Dns
+                } else {
+                    ErrorCode::Other
+                }
+            }
+        },
+        sqlx::Error::Tls(_) => ErrorCode::Tls,
+        sqlx::Error::PoolTimedOut => ErrorCode::Timeout,
+        sqlx::Error::Configuration(_) => ErrorCode::Config,
+        sqlx::Error::RowNotFound => ErrorCode::NotFound,
+        sqlx::Error::ColumnNotFound(_)
+        | sqlx::Error::ColumnIndexOutOfBounds { .. }
+        | sqlx::Error::ColumnDecode { .. }
+        | sqlx::Error::Decode(_) => ErrorCode::Decode,
+        sqlx::Error::Database(db) => {
+            if let Some(my) = db.try_downcast_ref::<MySqlDatabaseError>() {
+                return match my.number() {
+                    // ER_DBACCESS_DENIED_ERROR, ER_ACCESS_DENIED_ERROR, ER_ACCESS_DENIED_NO_PASSWORD_ERROR
+                    1044 | 1045 | 1698 => ErrorCode::Auth,
+                    // ER_BAD_DB_ERROR
+                    1049 => ErrorCode::UnknownDatabase,
+                    _ => ErrorCode::Query,
+                };
+            }
+            match db.code().as_deref() {
+                // invalid_authorization_specification, invalid_password
+                Some("28000") | Some("28P01") => ErrorCode::Auth,
+                // invalid_catalog_name
+                Some("3D000") => ErrorCode::UnknownDatabase,
+                // SQLITE_CANTOPEN
+                Some("14") => ErrorCode::UnknownDatabase,
+                // SQLITE_AUTH
+                Some("23") => ErrorCode::Auth,
+                _ => ErrorCode::Query,
+            }
+        }
+        _ => ErrorCode::Other,
+    }
+}
@@ -0,0 +1,266 @@
+//! 对象过滤规则
+//!
+//! 按对象类型用 glob 或正则表达式包含、排除对象，规则保存在 `DbConn.filter` 中，
+//! 命令也可以为单次任务传入规则代替保存的规则。
+//! 表名的 glob 规则由各驱动转换为 SQL 条件，不读取排除的表的列、索引等；
+//! 视图、触发器、存储过程等在 SQL 中不过滤，读取后由 `DbPool` 去掉排除的对象。
+//! 所有规则最后由 `Filter::matches` 统一检查，模式列表中的表数量按同样的规则统计，
+//! 差异对比、规范检查、文档和代码生成看到的对象相同。
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ErrorCode},
+    DResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectKind {
+    Table,
+    View,
+    Trigger,
+    Routine,
+    Event,
+    Sequence,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Syntax {
+    /// `*` 匹配任意个字符，`?` 匹配一个字符，需要匹配整个名称
+    Glob,
+    /// 匹配名称的一部分，需要匹配整个名称时使用 `^...$`
+    Regex,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterRule {
+    /// 适用的对象类型，为空时适用于所有类型
+    #[serde(default)]
+    pub kinds: Vec<ObjectKind>,
+    pub syntax: Syntax,
+    /// 匹配对象名，不包含模式名，区分大小写
+    pub pattern: String,
+    /// 排除匹配的对象，否则为只包含匹配的对象
+    #[serde(default)]
+    pub exclude: bool,
+}
+
+impl FilterRule {
+    fn applies(&self, kind: ObjectKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+}
+
+/// 保存的过滤规则。某类对象没有包含规则时包含所有对象，否则只包含匹配任一包含规则的对象，
+/// 再去掉匹配任一排除规则的对象
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ObjectFilter {
+    #[serde(default)]
+    pub rules: Vec<FilterRule>,
+}
+
+impl ObjectFilter {
+    /// 编译规则，正则表达式错误时返回出错的规则
+    pub fn compile(&self) -> DResult<Filter> {
+        let rules = self
+            .rules
+            .iter()
+            .map(|rule| {
+                let pattern = match rule.syntax {
+                    Syntax::Glob => glob_regex(&rule.pattern),
+                    Syntax::Regex => rule.pattern.clone(),
+                };
+                Regex::new(&pattern)
+                    .map(|regex| (rule.clone(), regex))
+                    .map_err(|e| {
+                        Error::new(ErrorCode::Config, e.to_string()).with_object(&rule.pattern)
+                    })
+            })
+            .collect::<DResult<_>>()?;
+        Ok(Filter { rules })
+    }
+}
+
+/// 编译后的过滤规则
+#[derive(Debug, Default)]
+pub struct Filter {
+    rules: Vec<(FilterRule, Regex)>,
+}
+
+impl Filter {
+    /// 对象是否保留
+    pub fn matches(&self, kind: ObjectKind, name: &str) -> bool {
+        let mut included = None;
+        for (rule, regex) in self.rules.iter().filter(|(r, _)| r.applies(kind)) {
+            let hit = regex.is_match(name);
+            if rule.exclude {
+                if hit {
+                    return false;
+                }
+            } else {
+                included = Some(included.unwrap_or(false) || hit);
+            }
+        }
+        included.unwrap_or(true)
+    }
+
+    /// 是否有适用于 `kind` 的规则
+    pub fn has_rules(&self, kind: ObjectKind) -> bool {
+        self.rules.iter().any(|(r, _)| r.applies(kind))
+    }
+
+    pub fn retain<T>(&self, kind: ObjectKind, items: &mut Vec<T>, name: impl Fn(&T) -> &str) {
+        items.retain(|item| self.matches(kind, name(item)));
+    }
+
+    /// 可以在 SQL 中检查的表名 glob 规则：(包含的模式, 排除的模式)。
+    /// 正则表达式的语法各数据库不同，不转换；有正则表达式的包含规则时不转换包含规则
+    pub fn table_globs(&self) -> (Vec<&str>, Vec<&str>) {
+        let rules = self
+            .rules
+            .iter()
+            .map(|(r, _)| r)
+            .filter(|r| r.applies(ObjectKind::Table))
+            .collect::<Vec<_>>();
+        let globs = |exclude: bool| {
+            rules
+                .iter()
+                .filter(|r| r.exclude == exclude && r.syntax == Syntax::Glob)
+                .map(|r| r.pattern.as_str())
+                .collect::<Vec<_>>()
+        };
+        let regex_include = rules
+            .iter()
+            .any(|r| !r.exclude && r.syntax == Syntax::Regex);
+        let include = if regex_include {
+            Vec::new()
+        } else {
+            globs(false)
+        };
+        (include, globs(true))
+    }
+
+    /// 表名 glob 规则对应的 LIKE 条件，以 AND 开头，没有可以转换的规则时为空。
+    /// `name` 为区分大小写比较表名的 SQL 表达式，`param` 生成第 n 个参数的占位符，
+    /// 参数按顺序为返回的模式
+    pub fn table_condition(
+        &self,
+        name: &str,
+        param: impl Fn(usize) -> String,
+    ) -> (String, Vec<String>) {
+        let (include, exclude) = self.table_globs();
+        let mut sql = String::new();
+        if !include.is_empty() {
+            let likes = (0..include.len())
+                .map(|n| format!("{name} LIKE {} ESCAPE '!'", param(n)))
+                .collect::<Vec<_>>();
+            sql.push_str(&format!(" AND ({})", likes.join(" OR ")));
+        }
+        for n in include.len()..include.len() + exclude.len() {
+            sql.push_str(&format!(" AND {name} NOT LIKE {} ESCAPE '!'", param(n)));
+        }
+        let patterns = include
+            .into_iter()
+            .chain(exclude)
+            .map(like_pattern)
+            .collect();
+        (sql, patterns)
+    }
+}
+
+/// glob 转换为匹配整个名称的正则表达式
+fn glob_regex(glob: &str) -> String {
+    let mut res = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => res.push_str(".*"),
+            '?' => res.push('.'),
+            c => res.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    res.push('$');
+    res
+}
+
+/// glob 转换为 LIKE 模式，转义字符为 `!`
+fn like_pattern(glob: &str) -> String {
+    let mut res = String::new();
+    for c in glob.chars() {
+        match c {
+            '*' => res.push('%'),
+            '?' => res.push('_'),
+            '%' | '_' | '!' => {
+                res.push('!');
+                res.push(c);
+            }
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(syntax: Syntax, pattern: &str, exclude: bool) -> FilterRule {
+        FilterRule {
+            kinds: vec![ObjectKind::Table],
+            syntax,
+            pattern: pattern.to_string(),
+            exclude,
+        }
+    }
+
+    #[test]
+    fn include_and_exclude() {
+        let filter = ObjectFilter {
+            rules: vec![
+                rule(Syntax::Glob, "user*", false),
+                rule(Syntax::Regex, "^order_\\d+$", false),
+                rule(Syntax::Glob, "*_bak", true),
+            ],
+        }
+        .compile()
+        .unwrap();
+        assert!(filter.matches(ObjectKind::Table, "users"));
+        assert!(filter.matches(ObjectKind::Table, "order_1"));
+        assert!(!filter.matches(ObjectKind::Table, "user_bak"));
+        assert!(!filter.matches(ObjectKind::Table, "orders"));
+        assert!(!filter.matches(ObjectKind::Table, "Users"));
+        // 只有表的规则，视图不过滤
+        assert!(filter.matches(ObjectKind::View, "orders"));
+        // 有正则表达式的包含规则，包含规则不转换为 SQL
+        assert_eq!(filter.table_globs(), (vec![], vec!["*_bak"]));
+    }
+
+    #[test]
+    fn like_condition() {
+        let filter = ObjectFilter {
+            rules: vec![
+                rule(Syntax::Glob, "tmp_*", true),
+                rule(Syntax::Glob, "a?c", false),
+            ],
+        }
+        .compile()
+        .unwrap();
+        let (sql, patterns) = filter.table_condition("name", |n| format!("${}", n + 1));
+        assert_eq!(
+            sql,
+            " AND (name LIKE $1 ESCAPE '!') AND name NOT LIKE $2 ESCAPE '!'"
+        );
+        assert_eq!(patterns, ["a_c", "tmp!_%"]);
+    }
+
+    #[test]
+    fn invalid_regex() {
+        let filter = ObjectFilter {
+            rules: vec![rule(Syntax::Regex, "(", false)],
+        };
+        let err = filter.compile().unwrap_err();
+        assert_eq!(err.object.as_deref(), Some("("));
+    }
+}
@@ -0,0 +1,333 @@
+//! 读取数据库结构
+//!
+//! 各数据库驱动实现 `Introspector`，返回 `lib.rs` 中统一的 `Table` 模型，
+//! 差异对比、规范检查和代码生成不需要关心连接使用的是哪种数据库。
+
+use std::collections::HashMap;
+
+use sqlx::{MySqlPool, PgPool, SqlitePool};
+
+use crate::{
+    filter::{Filter, ObjectKind},
+    mysql, pg,
+    pool::DbPool,
+    sqlite, DResult, Event, ForeignKey, Partition, Partitioning, Routine, Schema, Sequence, Table,
+    TableColumn, TableConstraint, TableIndex, Trigger, View,
+};
+
+/// 读取的范围
+#[derive(Debug, Default)]
+pub(crate) struct Scope {
+    /// 要读取的模式，为空时读取连接默认的模式：
+    /// MySQL 为 `DbConn.database`，PostgreSQL 为 `search_path` 中的模式，SQLite 为 main
+    pub schemas: Vec<String>,
+    /// 各驱动只在 SQL 中检查表名的 glob 规则，所有规则由 `DbPool` 统一检查
+    pub filter: Filter,
+}
+
+pub(crate) trait Introspector {
+    /// 可以读取的模式，不包含系统模式。MySQL 为服务器上的数据库，
+    /// PostgreSQL 为连接的数据库中的模式，SQLite 只有主数据库。表数量只统计 `filter` 保留的表
+    async fn schemas(&self, filter: &Filter) -> DResult<Vec<Schema>>;
+
+    /// 范围内的所有表
+    async fn tables(&self, scope: &Scope) -> DResult<Vec<Table>>;
+
+    /// 范围内的序列，不支持序列的数据库返回空
+    async fn sequences(&self, _scope: &Scope) -> DResult<Vec<Sequence>> {
+        Ok(Vec::new())
+    }
+
+    async fn views(&self, scope: &Scope) -> DResult<Vec<View>>;
+
+    async fn triggers(&self, scope: &Scope) -> DResult<Vec<Trigger>>;
+
+    /// 存储过程和函数，不支持的数据库返回空
+    async fn routines(&self, _scope: &Scope) -> DResult<Vec<Routine>> {
+        Ok(Vec::new())
+    }
+
+    /// 定时事件，不支持的数据库返回空
+    async fn events(&self, _scope: &Scope) -> DResult<Vec<Event>> {
+        Ok(Vec::new())
+    }
+}
+
+/// 按过滤规则重新统计各模式的表数量，`tables` 为 (模式名, 表名)
+pub fn count_tables(schemas: &mut [Schema], tables: &[(String, String)], filter: &Filter) {
+    for schema in schemas.iter_mut() {
+        schema.table_count = tables
+            .iter()
+            .filter(|(s, t)| *s == schema.schema_name && filter.matches(ObjectKind::Table, t))
+            .count() as u64;
+    }
+}
+
+impl Introspector for MySqlPool {
+    async fn schemas(&self, filter: &Filter) -> DResult<Vec<Schema>> {
+        mysql::schemas(self, filter).await
+    }
+
+    async fn tables(&self, scope: &Scope) -> DResult<Vec<Table>> {
+        mysql::table(self, &scope.schemas, &scope.filter).await
+    }
+
+    async fn views(&self, scope: &Scope) -> DResult<Vec<View>> {
+        mysql::views(self, &scope.schemas).await
+    }
+
+    async fn triggers(&self, scope: &Scope) -> DResult<Vec<Trigger>> {
+        mysql::triggers(self, &scope.schemas).await
+    }
+
+    async fn routines(&self, scope: &Scope) -> DResult<Vec<Routine>> {
+        mysql::routines(self, &scope.schemas).await
+    }
+
+    async fn events(&self, scope: &Scope) -> DResult<Vec<Event>> {
+        mysql::events(self, &scope.schemas).await
+    }
+}
+
+impl Introspector for PgPool {
+    async fn schemas(&self, filter: &Filter) -> DResult<Vec<Schema>> {
+        pg::schemas(self, filter).await
+    }
+
+    async fn tables(&self, scope: &Scope) -> DResult<Vec<Table>> {
+        pg::table(self, &scope.schemas, &scope.filter).await
+    }
+
+    async fn sequences(&self, scope: &Scope) -> DResult<Vec<Sequence>> {
+        pg::sequences(self, &scope.schemas).await
+    }
+
+    async fn views(&self, scope: &Scope) -> DResult<Vec<View>> {
+        pg::views(self, &scope.schemas).await
+    }
+
+    async fn triggers(&self, scope: &Scope) -> DResult<Vec<Trigger>> {
+        pg::triggers(self, &scope.schemas).await
+    }
+
+    async fn routines(&self, scope: &Scope) -> DResult<Vec<Routine>> {
+        pg::routines(self, &scope.schemas).await
+    }
+}
+
+impl Introspector for SqlitePool {
+    async fn schemas(&self, filter: &Filter) -> DResult<Vec<Schema>> {
+        sqlite::schemas(self, filter).await
+    }
+
+    async fn tables(&self, scope: &Scope) -> DResult<Vec<Table>> {
+        sqlite::table(self, &scope.schemas, &scope.filter).await
+    }
+
+    async fn views(&self, scope: &Scope) -> DResult<Vec<View>> {
+        sqlite::views(self, &scope.schemas).await
+    }
+
+    async fn triggers(&self, scope: &Scope) -> DResult<Vec<Trigger>> {
+        sqlite::triggers(self, &scope.schemas).await
+    }
+}
+
+impl Introspector for DbPool {
+    async fn schemas(&self, filter: &Filter) -> DResult<Vec<Schema>> {
+        match self {
+            DbPool::Mysql(p) => p.schemas(filter).await,
+            DbPool::Postgres(p) => p.schemas(filter).await,
+            DbPool::Sqlite(p) => p.schemas(filter).await,
+        }
+    }
+
+    async fn tables(&self, scope: &Scope) -> DResult<Vec<Table>> {
+        let mut tables = match self {
+            DbPool::Mysql(p) => p.tables(scope).await,
+            DbPool::Postgres(p) => p.tables(scope).await,
+            DbPool::Sqlite(p) => p.tables(scope).await,
+        }?;
+        scope
+            .filter
+            .retain(ObjectKind::Table, &mut tables, |t| &t.table_name);
+        Ok(tables)
+    }
+
+    async fn sequences(&self, scope: &Scope) -> DResult<Vec<Sequence>> {
+        let mut sequences = match self {
+            DbPool::Mysql(p) => p.sequences(scope).await,
+            DbPool::Postgres(p) => p.sequences(scope).await,
+            DbPool::Sqlite(p) => p.sequences(scope).await,
+        }?;
+        scope
+            .filter
+            .retain(ObjectKind::Sequence, &mut sequences, |s| &s.sequence_name);
+        Ok(sequences)
+    }
+
+    async fn views(&self, scope: &Scope) -> DResult<Vec<View>> {
+        let mut views = match self {
+            DbPool::Mysql(p) => p.views(scope).await,
+            DbPool::Postgres(p) => p.views(scope).await,
+            DbPool::Sqlite(p) => p.views(scope).await,
+        }?;
+        scope
+            .filter
+            .retain(ObjectKind::View, &mut views, |v| &v.view_name);
+        Ok(views)
+    }
+
+    /// 所在的表被排除的触发器同样排除
+    async fn triggers(&self, scope: &Scope) -> DResult<Vec<Trigger>> {
+        let mut triggers = match self {
+            DbPool::Mysql(p) => p.triggers(scope).await,
+            DbPool::Postgres(p) => p.triggers(scope).await,
+            DbPool::Sqlite(p) => p.triggers(scope).await,
+        }?;
+        triggers.retain(|t| {
+            scope.filter.matches(ObjectKind::Trigger, &t.trigger_name)
+                && scope.filter.matches(ObjectKind::Table, &t.table_name)
+        });
+        Ok(triggers)
+    }
+
+    async fn routines(&self, scope: &Scope) -> DResult<Vec<Routine>> {
+        let mut routines = match self {
+            DbPool::Mysql(p) => p.routines(scope).await,
+            DbPool::Postgres(p) => p.routines(scope).await,
+            DbPool::Sqlite(p) => p.routines(scope).await,
+        }?;
+        scope
+            .filter
+            .retain(ObjectKind::Routine, &mut routines, |r| &r.routine_name);
+        Ok(routines)
+    }
+
+    async fn events(&self, scope: &Scope) -> DResult<Vec<Event>> {
+        let mut events = match self {
+            DbPool::Mysql(p) => p.events(scope).await,
+            DbPool::Postgres(p) => p.events(scope).await,
+            DbPool::Sqlite(p) => p.events(scope).await,
+        }?;
+        scope
+            .filter
+            .retain(ObjectKind::Event, &mut events, |e| &e.event_name);
+        Ok(events)
+    }
+}
+
+/// 各驱动查询到的表的组成部分，由 `assemble` 放入对应的表
+#[derive(Default)]
+pub(crate) struct TableParts {
+    pub columns: Vec<TableColumn>,
+    /// 每行只有一个键，同一索引的多行合并为一个索引
+    pub indexs: Vec<TableIndex>,
+    pub constraints: Vec<TableConstraint>,
+    /// 每行只有一对列，同一外键的多行合并为一个外键
+    pub foreign_keys: Vec<ForeignKey>,
+    /// 每行只有一个分区和最多一个子分区，同一分区的多行合并为一个分区
+    pub partitionings: Vec<Partitioning>,
+}
+
+/// 按 (模式, 表名) 把各部分放入对应的表，找不到表的忽略。
+/// 列按 ordinal_position 排序，索引的键按 seq_in_index 排序，
+/// 索引和外键按首次出现的顺序
+pub(crate) fn assemble(tables: &mut [Table], parts: TableParts) {
+    let pos = tables
+        .iter()
+        .enumerate()
+        .map(|(i, t)| ((t.table_schema.clone(), t.table_name.clone()), i))
+        .collect::<HashMap<_, _>>();
+    let find = |schema: &str, name: &str| pos.get(&(schema.to_string(), name.to_string())).copied();
+
+    for column in parts.columns {
+        if let Some(i) = find(&column.table_schema, &column.table_name) {
+            tables[i].fileds.push(column);
+        }
+    }
+    for index in parts.indexs {
+        let Some(i) = find(&index.table_schema, &index.table_name) else {
+            continue;
+        };
+        let table = &mut tables[i];
+        match table
+            .indexs
+            .iter_mut()
+            .find(|exist| exist.index_name == index.index_name)
+        {
+            Some(exist) => exist.columns.extend(index.columns),
+            None => table.indexs.push(index),
+        }
+    }
+    for constraint in parts.constraints {
+        if let Some(i) = find(&constraint.table_schema, &constraint.table_name) {
+            tables[i].constraints.push(constraint);
+        }
+    }
+    for fk in parts.foreign_keys {
+        let Some(i) = find(&fk.table_schema, &fk.table_name) else {
+            continue;
+        };
+        let table = &mut tables[i];
+        match table
+            .foreign_keys
+            .iter_mut()
+            .find(|exist| exist.constraint_name == fk.constraint_name)
+        {
+            Some(exist) => {
+                exist.columns.extend(fk.columns);
+                exist.referenced_columns.extend(fk.referenced_columns);
+            }
+            None => table.foreign_keys.push(fk),
+        }
+    }
+    for partitioning in parts.partitionings {
+        let Some(i) = find(&partitioning.table_schema, &partitioning.table_name) else {
+            continue;
+        };
+        let table = &mut tables[i];
+        match &mut table.partitioning {
+            Some(exist) => {
+                for partition in partitioning.partitions {
+                    merge_partition(&mut exist.partitions, partition);
+                }
+                if exist.subpartition_method.is_none() {
+                    exist.subpartition_method = partitioning.subpartition_method;
+                    exist.subpartition_expression = partitioning.subpartition_expression;
+                }
+            }
+            None => table.partitioning = Some(partitioning),
+        }
+    }
+
+    for table in tables.iter_mut() {
+        table.fileds.sort_by_key(|c| c.ordinal_position);
+        for index in &mut table.indexs {
+            index.columns.sort_by_key(|p| p.seq_in_index);
+        }
+        if let Some(partitioning) = &mut table.partitioning {
+            partitioning.partitions.sort_by_key(|p| p.ordinal_position);
+            for partition in &mut partitioning.partitions {
+                partition.subpartitions.sort_by_key(|p| p.ordinal_position);
+            }
+        }
+    }
+}
+
+/// 同名分区合并子分区，行数相加
+fn merge_partition(partitions: &mut Vec<Partition>, partition: Partition) {
+    match partitions
+        .iter_mut()
+        .find(|exist| exist.partition_name == partition.partition_name)
+    {
+        Some(exist) => {
+            exist.table_rows = match (exist.table_rows, partition.table_rows) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            };
+            exist.subpartitions.extend(partition.subpartitions);
+        }
+        None => partitions.push(partition),
+    }
+}
@@ -4,19 +4,38 @@ use std::{collections::HashMap, sync::Mutex};
 
 use serde::{Deserialize, Serialize};
 use sqlx::{
-    any::AnyPoolOptions, Any, AnyPool, MySql, MySqlPool, PgPool, Pool, Postgres, Row, Sqlite,
-    SqlitePool,
+    mysql::MySqlConnectOptions, postgres::PgConnectOptions, sqlite::SqliteConnectOptions, Any,
+    AnyPool, MySql, Pool, Postgres, Sqlite,
 };
+use tauri::Manager;
 
+use error::{Error, ErrorCode};
+use introspect::Introspector;
+
+mod coltype;
+mod conn_test;
+mod error;
+mod filter;
+mod import;
+mod introspect;
 mod mysql;
+mod options;
+mod pg;
+mod pool;
+mod record;
+mod report;
+mod sqlite;
+mod store;
+mod tunnel;
+mod vault;
 
 lazy_static::lazy_static! {
     static ref DBConnMap: Mutex<HashMap<u64, DbConn>> = Mutex::new(HashMap::new());
 }
 
-pub type DResult<T, E = &'static str> = std::result::Result<T, E>;
+pub type DResult<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Driver {
     Mysql,
@@ -24,7 +43,18 @@ pub enum Driver {
     Sqlite,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 连接所属环境
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Env {
+    #[default]
+    Dev,
+    Test,
+    Staging,
+    Prod,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct DbConn {
     #[serde(default)]
     id: u64,
@@ -33,52 +63,180 @@ struct DbConn {
     host: String,
     port: u16,
     username: String,
-    password: String,
+    /// 明文密码，只在编辑或测试连接时由界面传入，不会保存也不会返回给界面。
+    /// 编辑时为 None 表示保持原密码不变
+    #[serde(default, skip_serializing)]
+    password: Option<String>,
+    /// 加密后的密码，见 `vault`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret: Option<String>,
     database: String,
+    /// 驱动连接参数
+    #[serde(default)]
+    options: options::DriverOptions,
+    /// TLS 配置，SQLite 忽略
+    #[serde(default)]
+    tls: options::TlsConfig,
+    /// SSH 隧道配置
+    #[serde(default)]
+    ssh: tunnel::SshConfig,
+    /// 连接池配置
+    #[serde(default)]
+    pool: pool::PoolConfig,
+    /// 所属环境
+    #[serde(default)]
+    env: Env,
+    /// 所在分组，多级用 / 分隔，空字符串表示未分组
+    #[serde(default)]
+    folder: String,
+    /// 受保护的连接使用只读会话，执行 DDL、DML 需要每次显式确认，见 `ensure_writable`。
+    /// MySQL 的只读会话（`SET SESSION TRANSACTION READ ONLY`）只阻止 DML，不阻止 DDL
+    #[serde(default)]
+    protected: bool,
+    /// 读取结构时的对象过滤规则，命令传入的规则优先
+    #[serde(default)]
+    filter: filter::ObjectFilter,
+}
+
+/// 返回给界面的连接信息，不包含密码
+#[derive(Debug, Serialize)]
+struct DbConnView {
+    #[serde(flatten)]
+    conn: DbConn,
+    /// 是否已保存密码
+    has_password: bool,
+}
+
+impl From<&DbConn> for DbConnView {
+    fn from(conn: &DbConn) -> Self {
+        let mut view = DbConn {
+            password: None,
+            secret: None,
+            ..conn.clone()
+        };
+        view.ssh.redact();
+        Self {
+            conn: view,
+            has_password: conn.secret.is_some(),
+        }
+    }
+}
+
+/// 处理界面传入的明文：None 保持原密文，空字符串清除，否则加密
+fn seal(plain: Option<String>, old: Option<String>) -> DResult<Option<String>> {
+    match plain {
+        Some(plain) if plain.is_empty() => Ok(None),
+        Some(plain) => vault::encrypt(&plain).map(Some),
+        None => Ok(old),
+    }
 }
 
 impl DbConn {
-    fn url(&self) -> String {
-        match self.driver {
-            Driver::Mysql => format!(
-                "mysql://{}:{}@{}:{}/{}",
-                self.username, self.password, self.host, self.port, self.database
-            ),
-            Driver::Postgres => format!(
-                "postgres://{}:{}@{}:{}/{}",
-                self.username, self.password, self.host, self.port, self.database
-            ),
-            Driver::Sqlite => format!("sqlite://{}", self.database),
+    /// 除连接名、环境、分组、过滤规则外的连接信息是否相同，不同时需要重建连接池
+    fn same_target(&self, other: &DbConn) -> bool {
+        let strip = |c: &DbConn| DbConn {
+            name: String::new(),
+            env: Env::default(),
+            folder: String::new(),
+            filter: Default::default(),
+            ..c.clone()
+        };
+        strip(self) == strip(other)
+    }
+
+    /// 加密界面传入的密码，未传入的保留 `old` 中已保存的密文
+    fn seal_secrets(&mut self, old: Option<&DbConn>) -> DResult<()> {
+        self.secret = seal(self.password.take(), old.and_then(|o| o.secret.clone()))?;
+        self.ssh.password_secret = seal(
+            self.ssh.password.take(),
+            old.and_then(|o| o.ssh.password_secret.clone()),
+        )?;
+        self.ssh.passphrase_secret = seal(
+            self.ssh.passphrase.take(),
+            old.and_then(|o| o.ssh.passphrase_secret.clone()),
+        )?;
+        Ok(())
+    }
+
+    /// 未传入明文的密码使用 `saved` 中已保存的密文
+    fn fill_secrets(&mut self, saved: &DbConn) {
+        if self.password.is_none() {
+            self.secret = saved.secret.clone();
+        }
+        if self.ssh.password.is_none() {
+            self.ssh.password_secret = saved.ssh.password_secret.clone();
+        }
+        if self.ssh.passphrase.is_none() {
+            self.ssh.passphrase_secret = saved.ssh.passphrase_secret.clone();
+        }
+    }
+
+    /// 获取明文密码，优先使用界面传入的密码，否则解密已保存的密码
+    fn password(&self) -> DResult<String> {
+        if let Some(password) = &self.password {
+            return Ok(password.clone());
+        }
+        match &self.secret {
+            Some(secret) => vault::decrypt(secret),
+            None => Ok(String::new()),
         }
     }
+
+    /// 执行 DDL、DML 前检查，受保护的连接需要显式传入 `allow_protected`。
+    /// 供迁移、数据同步、导入数据等写操作的命令调用
+    #[allow(dead_code)]
+    fn ensure_writable(&self, allow_protected: bool) -> DResult<()> {
+        if self.protected && !allow_protected {
+            return Err(Error::new(
+                ErrorCode::Protected,
+                "connection is protected, write requires explicit confirmation",
+            )
+            .with_object(&self.name));
+        }
+        Ok(())
+    }
 }
 
 pub async fn conn_any(url: &str) -> DResult<Pool<Any>> {
     sqlx::any::install_default_drivers();
-    AnyPool::connect(url)
-        .await
-        .map_err(|_| "SQL connect failed")
+    AnyPool::connect(url).await.map_err(Error::from)
 }
 
-pub async fn conn_mysql(url: &str) -> DResult<Pool<MySql>> {
-    sqlx::any::install_default_drivers();
-    MySqlPool::connect(url)
-        .await
-        .map_err(|_| "MySQL connect failed")
+/// `read_only` 为 true 时每个连接建立后设置为只读事务
+pub async fn conn_mysql(
+    opts: MySqlConnectOptions,
+    cfg: &pool::PoolConfig,
+    read_only: bool,
+) -> DResult<Pool<MySql>> {
+    let mut options = cfg.options::<MySql>();
+    if read_only {
+        options = options.after_connect(|conn, _| {
+            Box::pin(async move {
+                sqlx::query("SET SESSION TRANSACTION READ ONLY")
+                    .execute(conn)
+                    .await?;
+                Ok(())
+            })
+        });
+    }
+    options.connect_with(opts).await.map_err(Error::from)
 }
 
-pub async fn conn_pg(url: &str) -> DResult<Pool<Postgres>> {
-    sqlx::any::install_default_drivers();
-    PgPool::connect(url)
+pub async fn conn_pg(opts: PgConnectOptions, cfg: &pool::PoolConfig) -> DResult<Pool<Postgres>> {
+    cfg.options::<Postgres>()
+        .connect_with(opts)
         .await
-        .map_err(|_| "MySQL connect failed")
+        .map_err(Error::from)
 }
 
-pub async fn conn_sqlite(url: &str) -> DResult<Pool<Sqlite>> {
-    sqlx::any::install_default_drivers();
-    SqlitePool::connect(url)
+pub async fn conn_sqlite(
+    opts: SqliteConnectOptions,
+    cfg: &pool::PoolConfig,
+) -> DResult<Pool<Sqlite>> {
+    cfg.options::<Sqlite>()
+        .connect_with(opts)
         .await
-        .map_err(|_| "MySQL connect failed")
+        .map_err(Error::from)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -103,10 +261,36 @@ struct TableColumn {
     datetime_precision: Option<u32>,
     /// 对于字符串列，字符集名称。
     character_set_name: Option<String>,
+    /// 对于字符串列，排序规则。PostgreSQL 使用数据库默认值时为数据库的 LC_COLLATE
+    collation_name: Option<String>,
+    /// 排序规则是否单独指定。MySQL 为与表的默认值不同，PostgreSQL 为与类型的默认值不同，
+    /// SQLite 为列定义中有 COLLATE
+    explicit_collation: bool,
     column_type: String,
+    /// 由 column_type 解析的类型
+    parsed_type: coltype::ColumnType,
+    /// 代码生成使用的 Rust 类型，可以为 NULL 时为 `Option<T>`
+    rust_type: String,
     column_key: String,
     privileges: Option<String>,
     column_comment: String,
+    /// 标识列的生成方式，ALWAYS 或 BY DEFAULT，仅 PostgreSQL
+    identity: Option<String>,
+    /// serial 或标识列使用的序列，仅 PostgreSQL
+    sequence: Option<String>,
+    /// 生成列的类型，VIRTUAL 或 STORED
+    generated: Option<String>,
+    /// 生成列的表达式
+    generation_expression: Option<String>,
+    /// MySQL EXTRA 的原文，如 `DEFAULT_GENERATED on update CURRENT_TIMESTAMP`
+    extra: Option<String>,
+    /// 插入时自动生成值，MySQL 为 auto_increment，PostgreSQL 为 serial 和标识列，
+    /// SQLite 为 AUTOINCREMENT
+    auto_increment: bool,
+    /// ON UPDATE 的表达式，如 `CURRENT_TIMESTAMP(3)`，仅 MySQL
+    on_update: Option<String>,
+    /// 默认值为表达式，如 `DEFAULT CURRENT_TIMESTAMP`、`DEFAULT (uuid())`，仅 MySQL
+    default_generated: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -119,14 +303,247 @@ struct TableIndex {
     non_unique: bool,
     /// 索引的名称。如果索引是主键，则名称总是 PRIMARY。
     index_name: String,
-    /// 索引中的列序列号，从 1 开始。
-    seq_in_index: u32,
-    /// 列名。
-    column_name: Option<String>,
+    /// 索引的各个键，按 seq_in_index 排序
+    columns: Vec<IndexPart>,
     /// 索引列中未说明的有关索引的信息
     comment: String,
     /// 创建索引时使用 COMMENT 属性为索引提供的任何注释。
     index_comment: String,
+    /// 索引方法，如 BTREE、HASH，PostgreSQL 为 btree、gin、gist 等
+    index_type: Option<String>,
+    /// 部分索引的 WHERE 条件
+    predicate: Option<String>,
+    /// 优化器是否可以使用，MySQL 为 IS_VISIBLE，PostgreSQL 为 indisvalid
+    visible: bool,
+}
+
+/// 索引的一个键
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexPart {
+    /// 索引中的列序列号，从 1 开始。
+    seq_in_index: u32,
+    /// 列名，表达式键为空。
+    column_name: Option<String>,
+    /// 表达式键的表达式，如 `lower(email)`
+    expression: Option<String>,
+    /// 列在索引中的排序方式。其值可以是 A（升序）、D（降序）或 NULL（未排序）。
+    collation: Option<String>,
+    /// 前缀索引的长度，如 `name(20)` 为 20，仅 MySQL
+    sub_part: Option<u32>,
+    /// 索引到这一键为止的唯一值的估计数量，需要 ANALYZE。
+    /// PostgreSQL 为该列的唯一值数量，SQLite 由 sqlite_stat1 估算
+    cardinality: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TableConstraint {
+    table_schema: String,
+    table_name: String,
+    constraint_name: String,
+    /// PRIMARY KEY、UNIQUE、CHECK、EXCLUDE，外键见 `ForeignKey`
+    constraint_type: String,
+    /// 约束定义，如 `CHECK (price > 0)`
+    definition: String,
+}
+
+/// 外键
+#[derive(Debug, Serialize, Deserialize)]
+struct ForeignKey {
+    table_schema: String,
+    table_name: String,
+    constraint_name: String,
+    /// 外键列，与 referenced_columns 一一对应
+    columns: Vec<String>,
+    referenced_schema: String,
+    referenced_table: String,
+    referenced_columns: Vec<String>,
+    /// CASCADE、SET NULL、SET DEFAULT、RESTRICT、NO ACTION
+    update_rule: String,
+    delete_rule: String,
+    /// 是否可延迟检查，MySQL 始终为 false
+    deferrable: bool,
+    /// 是否默认延迟到事务提交时检查
+    initially_deferred: bool,
+}
+
+/// 序列，仅 PostgreSQL
+#[derive(Debug, Serialize, Deserialize)]
+struct Sequence {
+    sequence_schema: String,
+    sequence_name: String,
+    data_type: String,
+    start_value: i64,
+    min_value: i64,
+    max_value: i64,
+    increment_by: i64,
+    cycle: bool,
+    /// 尚未使用过时为空
+    last_value: Option<i64>,
+}
+
+/// 表的存储统计，都是估计值。MySQL 8.0 默认缓存 24 小时，见 information_schema_stats_expiry
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TableStats {
+    /// 估计行数，PostgreSQL 为 n_live_tup
+    table_rows: Option<u64>,
+    /// 数据占用的字节数，PostgreSQL 包含 TOAST
+    data_length: Option<u64>,
+    /// 索引占用的字节数，MySQL InnoDB 不包含主键
+    index_length: Option<u64>,
+    /// 可回收的字节数。MySQL 为表空间中的空闲空间，PostgreSQL 按死元组的比例估算，
+    /// SQLite 为页中未使用的空间
+    data_free: Option<u64>,
+    /// 下一个自增值，PostgreSQL 见 `Sequence.last_value`
+    auto_increment: Option<u64>,
+    /// 仅 MySQL
+    create_time: Option<String>,
+    /// 最后一次修改的时间，仅 MySQL
+    update_time: Option<String>,
+}
+
+/// 分区表的分区方式
+#[derive(Debug, Serialize, Deserialize)]
+struct Partitioning {
+    table_schema: String,
+    table_name: String,
+    /// RANGE、LIST、HASH，MySQL 还有 RANGE COLUMNS、LIST COLUMNS、KEY、LINEAR HASH 等
+    method: String,
+    /// 分区表达式或分区列，MySQL 的 KEY () 分区为空
+    expression: Option<String>,
+    /// PostgreSQL 的各分区可以使用不同的子分区方式，这里为第一个有子分区的分区的方式
+    subpartition_method: Option<String>,
+    subpartition_expression: Option<String>,
+    /// 按 ordinal_position 排序
+    partitions: Vec<Partition>,
+}
+
+/// 分区或子分区
+#[derive(Debug, Serialize, Deserialize)]
+struct Partition {
+    partition_name: String,
+    /// 从 1 开始。PostgreSQL 的分区没有顺序，按名称排列
+    ordinal_position: u32,
+    /// 分区的范围，如 `VALUES LESS THAN (2024)`、`VALUES IN (1, 2)`、
+    /// `FOR VALUES FROM ('2024-01-01') TO ('2025-01-01')`，HASH 和 KEY 分区为空
+    bound: Option<String>,
+    /// 估计行数，有子分区时为子分区之和，PostgreSQL 未 ANALYZE 时为空
+    table_rows: Option<u64>,
+    comment: Option<String>,
+    subpartitions: Vec<Partition>,
+}
+
+/// 模式，MySQL 为数据库
+#[derive(Debug, Serialize, Deserialize)]
+struct Schema {
+    schema_name: String,
+    /// 默认字符集，PostgreSQL 和 SQLite 为数据库的编码
+    character_set: Option<String>,
+    /// 默认排序规则，PostgreSQL 为数据库的 LC_COLLATE
+    collation: Option<String>,
+    /// 表的数量，PostgreSQL 的分区不单独计算
+    table_count: u64,
+    /// 数据和索引占用的字节数，是估计值
+    size: Option<u64>,
+}
+
+/// 视图，PostgreSQL 包含物化视图
+#[derive(Debug, Serialize, Deserialize)]
+struct View {
+    view_schema: String,
+    view_name: String,
+    /// 视图的查询语句
+    definition: String,
+    /// WITH CHECK OPTION 的级别，LOCAL 或 CASCADED，没有时为空
+    check_option: Option<String>,
+    materialized: bool,
+    /// MySQL 为 `user@host`，PostgreSQL 为所有者，SQLite 为空
+    definer: Option<String>,
+    /// DEFINER 或 INVOKER，SQLite 为空
+    security_type: Option<String>,
+}
+
+/// 触发器
+#[derive(Debug, Serialize, Deserialize)]
+struct Trigger {
+    trigger_schema: String,
+    trigger_name: String,
+    table_name: String,
+    /// BEFORE、AFTER、INSTEAD OF
+    timing: String,
+    /// INSERT、UPDATE、DELETE、TRUNCATE，MySQL 只有一个
+    events: Vec<String>,
+    /// ROW 或 STATEMENT
+    orientation: String,
+    /// 同一时机和事件的触发器的执行顺序，仅 MySQL
+    action_order: Option<i64>,
+    /// MySQL 为触发器体，PostgreSQL 和 SQLite 为完整的 CREATE TRIGGER 语句
+    definition: String,
+    definer: Option<String>,
+    /// 创建时的 sql_mode，仅 MySQL
+    sql_mode: Option<String>,
+}
+
+/// 存储过程和函数
+#[derive(Debug, Serialize, Deserialize)]
+struct Routine {
+    routine_schema: String,
+    routine_name: String,
+    /// 区分同名的重载函数，MySQL 与 routine_name 相同
+    specific_name: String,
+    /// PROCEDURE 或 FUNCTION
+    routine_type: String,
+    parameters: Vec<RoutineParameter>,
+    /// 函数的返回类型，存储过程为空
+    return_type: Option<String>,
+    /// SQL、plpgsql 等
+    language: String,
+    /// MySQL 为过程体，PostgreSQL 为完整的 CREATE FUNCTION 语句。没有权限时为空
+    definition: Option<String>,
+    deterministic: bool,
+    /// CONTAINS SQL、NO SQL、READS SQL DATA、MODIFIES SQL DATA，仅 MySQL
+    data_access: Option<String>,
+    definer: Option<String>,
+    /// DEFINER 或 INVOKER
+    security_type: String,
+    /// 创建时的 sql_mode，仅 MySQL
+    sql_mode: Option<String>,
+    comment: Option<String>,
+}
+
+/// 存储过程和函数的参数
+#[derive(Debug, Serialize, Deserialize)]
+struct RoutineParameter {
+    /// 从 1 开始
+    ordinal_position: u32,
+    /// IN、OUT、INOUT，PostgreSQL 还有 VARIADIC 和 RETURNS TABLE 中的 TABLE
+    mode: String,
+    /// 未命名的参数为空
+    name: Option<String>,
+    data_type: String,
+}
+
+/// 定时事件，仅 MySQL
+#[derive(Debug, Serialize, Deserialize)]
+struct Event {
+    event_schema: String,
+    event_name: String,
+    definition: String,
+    /// ONE TIME 或 RECURRING
+    event_type: String,
+    /// ONE TIME 事件的执行时间
+    execute_at: Option<String>,
+    /// RECURRING 事件的间隔，如 `1 DAY`
+    interval: Option<String>,
+    starts: Option<String>,
+    ends: Option<String>,
+    /// ENABLED、DISABLED、SLAVESIDE_DISABLED
+    status: String,
+    /// PRESERVE 或 NOT PRESERVE
+    on_completion: String,
+    definer: String,
+    time_zone: String,
+    sql_mode: String,
+    comment: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -145,58 +562,277 @@ struct Table {
     engine: Option<String>,
     /// 创建表格时使用的注释（或 MySQL 无法访问表格信息的原因）。
     table_comment: Option<String>,
-    fileds: HashMap<String, TableColumn>,
-    indexs: HashMap<String, TableIndex>,
+    /// 表的默认字符集和排序规则，仅 MySQL
+    character_set: Option<String>,
+    table_collation: Option<String>,
+    /// 建表选项，SQLite 为 WITHOUT ROWID、STRICT
+    create_options: Option<String>,
+    /// 列，按 ordinal_position 排序
+    fileds: Vec<TableColumn>,
+    indexs: Vec<TableIndex>,
+    constraints: Vec<TableConstraint>,
+    foreign_keys: Vec<ForeignKey>,
+    /// 分区表的分区方式，不是分区表时为空
+    partitioning: Option<Partitioning>,
+    /// 分区表为所有分区之和
+    stats: TableStats,
 }
 
 /// 获取数据库连接信息
 #[tauri::command]
-fn dbconn_list<'a>() -> Vec<DbConn> {
+fn dbconn_list() -> Vec<DbConnView> {
     DBConnMap
         .lock()
         .unwrap()
         .values()
-        .cloned()
+        .map(DbConnView::from)
         .collect::<Vec<_>>()
 }
 
+fn next_id(conns: &HashMap<u64, DbConn>) -> u64 {
+    let id_max = conns.keys().max();
+    match id_max {
+        Some(id) => id + 1,
+        None => 1,
+    }
+}
+
 /// 编辑数据库连接
 #[tauri::command]
-fn edit_dbconn(mut conn: DbConn) -> DResult<DbConn> {
+fn edit_dbconn(mut conn: DbConn) -> DResult<DbConnView> {
     let mut lock = DBConnMap.lock().unwrap();
-    if lock.get(&conn.id).is_none() {
-        let id_max = lock.keys().max();
-        let id = match id_max {
-            Some(id) => id + 1,
-            None => 1,
-        };
-        conn.id = id;
+    let old = lock.get(&conn.id).cloned();
+    if old.is_none() {
+        conn.id = next_id(&lock);
+    }
+    conn.filter.compile()?;
+    conn.seal_secrets(old.as_ref())?;
+    // 先保存修改后的副本，保存成功后再替换内存中的连接
+    let mut conns = lock.clone();
+    conns.insert(conn.id, conn.clone());
+    store::save(&conns)?;
+    *lock = conns;
+    if old.is_some_and(|old| !old.same_target(&conn)) {
+        pool::invalidate(conn.id);
     }
-    lock.insert(conn.id, conn.clone());
-    Ok(conn)
+    Ok(DbConnView::from(&conn))
 }
 
 /// 删除数据库连接
 #[tauri::command]
-fn del_dbconn(id: u64) {
-    DBConnMap.lock().unwrap().remove(&id);
-}
-
-///
-/// 生成数据库差异报告
-/// 下载数据库差异报告,png,pdf,word
-///
-/// 生成结构差异SQL
-/// 生成数据差异SQL
-///
-/// 数据库规范检查
-/// 数据库规范检查结果下载
-/// 设置忽略检查拼写的单词
-/// 设置忽略检查复数的单词
-/// 自定义检查规范
-///
-/// 逆向生成代码配置
-/// 逆向生成代码
+fn del_dbconn(id: u64) -> DResult<()> {
+    let mut lock = DBConnMap.lock().unwrap();
+    if lock.contains_key(&id) {
+        let mut conns = lock.clone();
+        conns.remove(&id);
+        store::save(&conns)?;
+        *lock = conns;
+        pool::invalidate(id);
+    }
+    Ok(())
+}
+
+/// 测试数据库连接，连接可以是尚未保存的。
+/// 未传入密码时使用已保存的密码
+#[tauri::command]
+async fn test_dbconn(mut conn: DbConn) -> DResult<conn_test::ConnTest> {
+    let saved = DBConnMap.lock().unwrap().get(&conn.id).cloned();
+    if let Some(saved) = saved {
+        conn.fill_secrets(&saved);
+    }
+    conn_test::test(&conn).await
+}
+
+fn load_dbconns(conns: Vec<DbConn>) {
+    DBConnMap
+        .lock()
+        .unwrap()
+        .extend(conns.into_iter().map(|c| (c.id, c)));
+}
+
+/// 保存密码的密钥状态，钥匙串不可用时需要输入主密码
+#[tauri::command]
+fn vault_status() -> vault::VaultStatus {
+    vault::status()
+}
+
+/// 用主密码解锁，第一次调用时设置主密码。启动时未能加载的连接在解锁后加载
+#[tauri::command]
+fn unlock_vault(passphrase: String) -> DResult<()> {
+    vault::unlock(&passphrase)?;
+    if !store::loaded() {
+        load_dbconns(store::load()?);
+    }
+    Ok(())
+}
+
+/// 读取结构的范围。没有指定 `schemas` 时读取连接默认的模式，
+/// 没有传入 `filter` 时使用连接保存的过滤规则
+fn scope(
+    id: u64,
+    schemas: Option<Vec<String>>,
+    filter: Option<filter::ObjectFilter>,
+) -> DResult<introspect::Scope> {
+    let filter = match filter {
+        Some(filter) => filter,
+        None => DBConnMap
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|c| c.filter.clone())
+            .unwrap_or_default(),
+    };
+    Ok(introspect::Scope {
+        schemas: schemas.unwrap_or_default(),
+        filter: filter.compile()?,
+    })
+}
+
+/// 获取已保存连接的表结构
+#[tauri::command]
+async fn table_list(
+    id: u64,
+    schemas: Option<Vec<String>>,
+    filter: Option<filter::ObjectFilter>,
+) -> DResult<Vec<Table>> {
+    let scope = scope(id, schemas, filter)?;
+    pool::get(id).await?.tables(&scope).await
+}
+
+/// 获取已保存连接的序列
+#[tauri::command]
+async fn sequence_list(
+    id: u64,
+    schemas: Option<Vec<String>>,
+    filter: Option<filter::ObjectFilter>,
+) -> DResult<Vec<Sequence>> {
+    let scope = scope(id, schemas, filter)?;
+    pool::get(id).await?.sequences(&scope).await
+}
+
+/// 已保存连接的存储空间报告，按模式汇总
+#[tauri::command]
+async fn size_report(
+    id: u64,
+    schemas: Option<Vec<String>>,
+    filter: Option<filter::ObjectFilter>,
+) -> DResult<Vec<report::SchemaSize>> {
+    let scope = scope(id, schemas, filter)?;
+    let pool = pool::get(id).await?;
+    let (tables, sequences) = tokio::try_join!(pool.tables(&scope), pool.sequences(&scope))?;
+    let sqlite = matches!(pool, pool::DbPool::Sqlite(_));
+    Ok(report::size_report(&tables, &sequences, sqlite))
+}
+
+/// 获取已保存连接可以读取的模式，以及各模式的表数量和大小。
+/// PostgreSQL 只包含连接的数据库中的模式，其他数据库需要另外保存连接。
+/// 表数量只统计过滤规则保留的表，没有传入 `filter` 时使用连接保存的规则
+#[tauri::command]
+async fn schema_list(id: u64, filter: Option<filter::ObjectFilter>) -> DResult<Vec<Schema>> {
+    let scope = scope(id, None, filter)?;
+    pool::get(id).await?.schemas(&scope.filter).await
+}
+
+/// 获取已保存连接的视图
+#[tauri::command]
+async fn view_list(
+    id: u64,
+    schemas: Option<Vec<String>>,
+    filter: Option<filter::ObjectFilter>,
+) -> DResult<Vec<View>> {
+    let scope = scope(id, schemas, filter)?;
+    pool::get(id).await?.views(&scope).await
+}
+
+/// 获取已保存连接的触发器
+#[tauri::command]
+async fn trigger_list(
+    id: u64,
+    schemas: Option<Vec<String>>,
+    filter: Option<filter::ObjectFilter>,
+) -> DResult<Vec<Trigger>> {
+    let scope = scope(id, schemas, filter)?;
+    pool::get(id).await?.triggers(&scope).await
+}
+
+/// 获取已保存连接的存储过程和函数
+#[tauri::command]
+async fn routine_list(
+    id: u64,
+    schemas: Option<Vec<String>>,
+    filter: Option<filter::ObjectFilter>,
+) -> DResult<Vec<Routine>> {
+    let scope = scope(id, schemas, filter)?;
+    pool::get(id).await?.routines(&scope).await
+}
+
+/// 获取已保存连接的定时事件
+#[tauri::command]
+async fn event_list(
+    id: u64,
+    schemas: Option<Vec<String>>,
+    filter: Option<filter::ObjectFilter>,
+) -> DResult<Vec<Event>> {
+    let scope = scope(id, schemas, filter)?;
+    pool::get(id).await?.events(&scope).await
+}
+
+/// 预览从其他工具或配置文件导入的连接
+#[tauri::command]
+fn import_preview(
+    source: import::ImportSource,
+    path: Option<String>,
+) -> DResult<Vec<import::ImportItem>> {
+    let exists = DBConnMap
+        .lock()
+        .unwrap()
+        .values()
+        .cloned()
+        .collect::<Vec<_>>();
+    let items = import::parse(source, path.as_deref(), &exists)?;
+    Ok(import::preview(&items))
+}
+
+/// 导入连接，跳过重复的连接，返回新建的连接
+#[tauri::command]
+fn import_dbconn(source: import::ImportSource, path: Option<String>) -> DResult<Vec<DbConnView>> {
+    let mut lock = DBConnMap.lock().unwrap();
+    let exists = lock.values().cloned().collect::<Vec<_>>();
+    let items = import::parse(source, path.as_deref(), &exists)?;
+
+    let mut conns = lock.clone();
+    let mut created = Vec::new();
+    for (mut conn, duplicate) in items {
+        if duplicate {
+            continue;
+        }
+        conn.id = next_id(&conns);
+        conn.seal_secrets(None)?;
+        created.push(DbConnView::from(&conn));
+        conns.insert(conn.id, conn);
+    }
+    if !created.is_empty() {
+        store::save(&conns)?;
+        *lock = conns;
+    }
+    Ok(created)
+}
+
+//
+// 生成数据库差异报告
+// 下载数据库差异报告,png,pdf,word
+//
+// 生成结构差异SQL
+// 生成数据差异SQL
+//
+// 数据库规范检查
+// 数据库规范检查结果下载
+// 设置忽略检查拼写的单词
+// 设置忽略检查复数的单词
+// 自定义检查规范
+//
+// 逆向生成代码配置
+// 逆向生成代码
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -206,11 +842,60 @@ pub fn run() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            let dir = app.path().app_config_dir()?;
+            match store::init(dir) {
+                Ok(conns) => load_dbconns(conns),
+                // 需要主密码才能升级旧的连接文件，在 `unlock_vault` 中加载
+                Err(e) if e.code == ErrorCode::VaultLocked => {}
+                Err(e) => return Err(e.into()),
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             dbconn_list,
             edit_dbconn,
-            del_dbconn
+            del_dbconn,
+            test_dbconn,
+            vault_status,
+            unlock_vault,
+            schema_list,
+            table_list,
+            sequence_list,
+            view_list,
+            trigger_list,
+            routine_list,
+            event_list,
+            size_report,
+            import_preview,
+            import_dbconn
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protected_write() {
+        let mut conn: DbConn = serde_json::from_value(serde_json::json!({
+            "driver": "mysql",
+            "name": "prod",
+            "host": "127.0.0.1",
+            "port": 3306,
+            "username": "root",
+            "password": "",
+            "database": "shop",
+        }))
+        .unwrap();
+        assert!(conn.ensure_writable(false).is_ok());
+
+        conn.protected = true;
+        let err = conn.ensure_writable(false).unwrap_err();
+        assert!(matches!(err.code, ErrorCode::Protected));
+        assert_eq!(err.object.as_deref(), Some("prod"));
+        assert!(conn.ensure_writable(true).is_ok());
+    }
+}
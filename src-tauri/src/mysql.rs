@@ -1,7 +1,16 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
-use sqlx::{any::AnyRow, mysql::MySqlRow, FromRow, Pool, Row};
+use sqlx::{mysql::MySqlArguments, query::Query, MySql, MySqlPool};
 
-use crate::DResult;
+use crate::{
+    coltype,
+    error::Error,
+    filter::{Filter, ObjectKind},
+    introspect,
+    record::Record,
+    DResult, Driver,
+};
 
 const MYSQL_SHOW_TABLE_CREATE: &str = "show create table ";
 
@@ -44,7 +53,7 @@ const DDL_DEFAULT: &str = " default ";
 /// | CREATE_OPTIONS  | varchar(256)                                                       | YES  |     | NULL    |       |
 /// | TABLE_COMMENT   | text                                                               | YES  |     | NULL    |       |
 /// +-----------------+--------------------------------------------------------------------+------+-----+---------+-------+
-#[derive(Default, Debug, Serialize, Deserialize, FromRow)]
+#[derive(Default, Debug, Serialize, Deserialize)]
 struct Table {
     // /// 表所属目录的名称。该值始终为 def。
     // table_catalog: String,
@@ -68,46 +77,46 @@ struct Table {
     // ///
     // /// enum('Fixed','Dynamic','Compressed','Redundant','Compact','Paged')
     // row_format: Option<String>,
-    // /// 行数。
-    // /// 某些存储引擎（如 MyISAM）会存储精确的计数。对于 InnoDB 等其他存储引擎，
-    // /// 该值只是一个近似值，可能与实际值相差 40% 到 50%。在这种情况下，
-    // /// 请使用 SELECT COUNT(*) 获得准确的计数。
-    // /// 对于 INFORMATION_SCHEMA 表，TABLE_ROWS 为空。
-    // /// 对于 InnoDB 表，行计数只是用于 SQL 优化的粗略估计值。(如果 InnoDB 表是分区表，情况也是如此）。
-    // table_rows: Option<u64>,
+    /// 行数。
+    /// 某些存储引擎（如 MyISAM）会存储精确的计数。对于 InnoDB 等其他存储引擎，
+    /// 该值只是一个近似值，可能与实际值相差 40% 到 50%。在这种情况下，
+    /// 请使用 SELECT COUNT(*) 获得准确的计数。
+    /// 对于 INFORMATION_SCHEMA 表，TABLE_ROWS 为空。
+    /// 对于 InnoDB 表，行计数只是用于 SQL 优化的粗略估计值。(如果 InnoDB 表是分区表，情况也是如此）。
+    table_rows: Option<u64>,
     // /// 平均行长。
     // avg_row_length: Option<u64>,
-    // /// 对于 MyISAM，DATA_LENGTH 是数据文件的长度，单位为字节。
-    // /// 对于 InnoDB，DATA_LENGTH 是为聚簇索引分配的大致空间大小，单位为字节。
-    // /// 具体来说，它是以页面为单位的聚类索引大小乘以 InnoDB 页面大小。
-    // data_length: Option<u64>,
+    /// 对于 MyISAM，DATA_LENGTH 是数据文件的长度，单位为字节。
+    /// 对于 InnoDB，DATA_LENGTH 是为聚簇索引分配的大致空间大小，单位为字节。
+    /// 具体来说，它是以页面为单位的聚类索引大小乘以 InnoDB 页面大小。
+    data_length: Option<u64>,
     // /// 对于 MyISAM，MAX_DATA_LENGTH 是数据文件的最大长度。
     // /// 这是在使用数据指针大小的情况下，表中可存储数据的总字节数。
     // /// 未用于 InnoDB。
     // max_data_length: Option<u64>,
-    // /// 对于 MyISAM，INDEX_LENGTH 是索引文件的长度，单位为字节。
-    // /// 对于 InnoDB，INDEX_LENGTH 是为非聚类索引分配的大致空间大小，单位为字节。
-    // /// 具体来说，它是以页面为单位的非聚类索引大小之和乘以 InnoDB 页面大小。
-    // index_length: Option<u64>,
-    // /// 已分配但未使用的字节数。
-    // /// InnoDB 表会报告表所属表空间的可用空间。对于共享表空间中的表，这是共享表空间的可用空间。如果使用的是多个表空间，且表有自己的表空间，则可用空间仅指该表的可用空间。可用空间是指完全空闲的扩展区中的字节数减去安全系数。即使可用空间显示为 0，只要不需要分配新的扩展，就可以插入行。
-    // /// 对于 NDB 群集，DATA_FREE 显示磁盘上为磁盘数据表或磁盘片段分配但未使用的空间。(内存数据资源使用情况由 DATA_LENGTH 列报告）。
-    // /// 对于分区表，该值只是一个估计值，不一定绝对正确。
-    // /// 在这种情况下，获取该信息的更准确方法是查询 INFORMATION_SCHEMA PARTITIONS 表
-    // data_free: Option<u64>,
-    // /// 下一个 AUTO_INCREMENT 值。
-    // auto_increment: Option<u64>,
-    // /// 创建表格的时间。
-    // create_time: String,
-    // /// 表最后一次更新的时间。对于某些存储引擎，该值为空。即使在每个 InnoDB 表都位于单独 .ibd文件中的按表生成文件模式下，更改缓冲也会延迟写入数据文件，因此文件修改时间与上次插入、更新或删除的时间不同。对于 MyISAM，使用的是数据文件时间戳；但在 Windows 上，时间戳不会因更新而更新，因此该值并不准确。
-    // /// UPDATE_TIME 显示在未分区的 InnoDB 表上执行的最后一次 UPDATE、INSERT 或 DELETE 的时间戳值。对于 MVCC，时间戳值反映的是 COMMIT 时间，即最后一次更新时间。服务器重启或表从 InnoDB 数据字典缓存中删除时，时间戳不会被持久化。
-    // update_time: Option<String>,
+    /// 对于 MyISAM，INDEX_LENGTH 是索引文件的长度，单位为字节。
+    /// 对于 InnoDB，INDEX_LENGTH 是为非聚类索引分配的大致空间大小，单位为字节。
+    /// 具体来说，它是以页面为单位的非聚类索引大小之和乘以 InnoDB 页面大小。
+    index_length: Option<u64>,
+    /// 已分配但未使用的字节数。
+    /// InnoDB 表会报告表所属表空间的可用空间。对于共享表空间中的表，这是共享表空间的可用空间。如果使用的是多个表空间，且表有自己的表空间，则可用空间仅指该表的可用空间。可用空间是指完全空闲的扩展区中的字节数减去安全系数。即使可用空间显示为 0，只要不需要分配新的扩展，就可以插入行。
+    /// 对于 NDB 群集，DATA_FREE 显示磁盘上为磁盘数据表或磁盘片段分配但未使用的空间。(内存数据资源使用情况由 DATA_LENGTH 列报告）。
+    /// 对于分区表，该值只是一个估计值，不一定绝对正确。
+    /// 在这种情况下，获取该信息的更准确方法是查询 INFORMATION_SCHEMA PARTITIONS 表
+    data_free: Option<u64>,
+    /// 下一个 AUTO_INCREMENT 值。
+    auto_increment: Option<u64>,
+    /// 创建表格的时间。
+    create_time: Option<String>,
+    /// 表最后一次更新的时间。对于某些存储引擎，该值为空。即使在每个 InnoDB 表都位于单独 .ibd文件中的按表生成文件模式下，更改缓冲也会延迟写入数据文件，因此文件修改时间与上次插入、更新或删除的时间不同。对于 MyISAM，使用的是数据文件时间戳；但在 Windows 上，时间戳不会因更新而更新，因此该值并不准确。
+    /// UPDATE_TIME 显示在未分区的 InnoDB 表上执行的最后一次 UPDATE、INSERT 或 DELETE 的时间戳值。对于 MVCC，时间戳值反映的是 COMMIT 时间，即最后一次更新时间。服务器重启或表从 InnoDB 数据字典缓存中删除时，时间戳不会被持久化。
+    update_time: Option<String>,
     // /// 最后一次检查表的时间。
     // /// 并非所有存储引擎都会更新这个时间，在这种情况下，该值始终为空。
     // /// 对于 InnoDB 分区表，CHECK_TIME 始终为空。
     // check_time: Option<String>,
-    // /// 表格默认校对方式。输出结果不会明确列出表格默认字符集，但校对名称以字符集名称开头。
-    // table_collation: Option<String>,
+    /// 表格默认校对方式。输出结果不会明确列出表格默认字符集，但校对名称以字符集名称开头。
+    table_collation: Option<String>,
     // /// 实时校验和值（如果有）。
     // checksum: Option<i64>,
     // /// 与 CREATE TABLE 一起使用的额外选项。
@@ -119,6 +128,8 @@ struct Table {
     // create_options: Option<String>,
     /// 创建表格时使用的注释（或 MySQL 无法访问表格信息的原因）。
     table_comment: Option<String>,
+    /// 由 TABLE_COLLATION 查询 COLLATIONS 得到的字符集，TABLES 中没有这一列
+    character_set: Option<String>,
 }
 
 /// +--------------------------+----------------------------+------+-----+---------+-------+
@@ -147,7 +158,7 @@ struct Table {
 /// | GENERATION_EXPRESSION    | longtext                   | NO   |     | NULL    |       |
 /// | SRS_ID                   | int unsigned               | YES  |     | NULL    |       |
 /// +--------------------------+----------------------------+------+-----+---------+-------+
-#[derive(Default, Debug, Serialize, Deserialize, FromRow)]
+#[derive(Default, Debug, Serialize, Deserialize)]
 struct TableColumn {
     // /// 包含列的表所属目录的名称。该值始终为 def
     // table_catalog: String,
@@ -185,8 +196,8 @@ struct TableColumn {
     datetime_precision: Option<u32>,
     /// 对于字符串列，字符集名称。
     character_set_name: Option<String>,
-    // /// 对于字符串列，校对名称。
-    // collation_name: Option<String>,
+    /// 对于字符串列，校对名称。
+    collation_name: Option<String>,
     /// 列数据类型。
     /// DATA_TYPE 值只有类型名称，没有其他信息。
     /// COLUMN_TYPE 值包含类型名称和其他信息，如精度或长度。
@@ -201,19 +212,19 @@ struct TableColumn {
     /// 如果有多个 COLUMN_KEY 值适用于表中的某一列，则 COLUMN_KEY 将按照 PRI、UNI、MUL 的顺序显示优先级最高的值。
     /// 如果 UNIQUE 索引不能包含 NULL 值，并且表中没有 PRIMARY KEY，则 UNIQUE 索引可能显示为 PRI。如果多个列组成一个复合 UNIQUE 索引，则 UNIQUE 索引可能显示为 MUL；虽然列的组合是唯一的，但每个列仍可包含给定值的多次出现。
     column_key: String,
-    // /// 关于给定列的任何附加信息。
-    // /// 在这些情况下，该值为非空值：
-    // /// auto_increment 用于具有 AUTO_INCREMENT 属性的列。
-    // /// ON UPDATE CURRENT_TIMESTAMP 属性的 TIMESTAMP 或 DATETIME 列的 ON UPDATE CURRENT_TIMESTAMP。
-    // /// STORED GENERATED 或 VIRTUAL GENERATED 用于生成列。
-    // /// DEFAULT_GENERATED 用于具有表达式默认值的列。
-    // extra: Option<String>,
+    /// 关于给定列的任何附加信息。
+    /// 在这些情况下，该值为非空值：
+    /// auto_increment 用于具有 AUTO_INCREMENT 属性的列。
+    /// ON UPDATE CURRENT_TIMESTAMP 属性的 TIMESTAMP 或 DATETIME 列的 ON UPDATE CURRENT_TIMESTAMP。
+    /// STORED GENERATED 或 VIRTUAL GENERATED 用于生成列。
+    /// DEFAULT_GENERATED 用于具有表达式默认值的列。
+    extra: Option<String>,
     /// 拥有的栏目权限。
     privileges: Option<String>,
     /// 列定义中包含的任何注释。
     column_comment: String,
-    // /// 对于生成的列，显示用于计算列值的表达式。非生成列为空。
-    // generation_expression: Option<String>,
+    /// 对于生成的列，显示用于计算列值的表达式。非生成列为空。
+    generation_expression: Option<String>,
     // /// 该值适用于空间列。
     // /// 它包含列 SRID 值，表示存储在列中的值的空间参照系统。
     // /// 对于非空间列和没有 SRID 属性的空间列，该值为 NULL。
@@ -242,7 +253,7 @@ struct TableColumn {
 /// | IS_VISIBLE    | varchar(3)    | NO   |     |         |       |
 /// | EXPRESSION    | longtext      | YES  |     | NULL    |       |
 /// +---------------+---------------+------+-----+---------+-------+
-#[derive(Default, Debug, Serialize, Deserialize, FromRow)]
+#[derive(Default, Debug, Serialize, Deserialize)]
 struct TableIndex {
     // /// 包含索引的表所属目录的名称。该值始终为 def。
     // table_catalog: String,
@@ -262,13 +273,13 @@ struct TableIndex {
     column_name: Option<String>,
     /// 列在索引中的排序方式。其值可以是 A（升序）、D（降序）或 NULL（未排序）。
     collation: Option<String>,
-    // /// 索引中唯一值的估计数量。
-    // /// 要更新这个数字，请运行 ANALYZE TABLE 或（对于 MyISAM 表）myisamchk -a。
-    // /// CARDINALITY 是根据以整数形式存储的统计数据计算的，因此即使对于小表，数值也不一定精确。卡片数越高，MySQL 在进行连接时使用索引的机会就越大。
-    // cardinality: Option<i64>,
-    // /// 索引前缀。
-    // /// 也就是说，如果列只有部分索引，则为索引字符数；如果整个列都有索引，则为 NULL。
-    // sub_part: Option<i64>,
+    /// 索引中唯一值的估计数量。
+    /// 要更新这个数字，请运行 ANALYZE TABLE 或（对于 MyISAM 表）myisamchk -a。
+    /// CARDINALITY 是根据以整数形式存储的统计数据计算的，因此即使对于小表，数值也不一定精确。卡片数越高，MySQL 在进行连接时使用索引的机会就越大。
+    cardinality: Option<i64>,
+    /// 索引前缀。
+    /// 也就是说，如果列只有部分索引，则为索引字符数；如果整个列都有索引，则为 NULL。
+    sub_part: Option<i64>,
     // /// 表示键的打包方式。否则为空。
     // packed: Option<Vec<u8>>,
     /// 如果列可能包含 NULL 值，则包含 "YES"；如果不包含 NULL 值，则包含"'"。
@@ -279,208 +290,951 @@ struct TableIndex {
     comment: String,
     /// 创建索引时使用 COMMENT 属性为索引提供的任何注释。
     index_comment: String,
-    // /// 优化器是否可见索引。
-    // is_visible: String,
-    // /// MySQL 8.0.13 及更高版本支持功能键部分（请参阅功能键部分），
-    // /// 这对 COLUMN_NAME 和 EXPRESSION 列都有影响：
-    // /// 对于非功能键部分，COLUMN_NAME 表示由键部分索引的列，EXPRESSION 为 NULL。
-    // /// 对于功能键部分，COLUMN_NAME 列为空，EXPRESSION 表示键部分的表达式。
-    // expression: Option<String>,
-}
-
-/// Rust type             MySQL type(s)
-/// bool                    TINYINT(1), BOOLEAN
-/// i8                      TINYINT
-/// i16                     SMALLINT
-/// i32                     INT
-/// i64                     BIGINT
-/// u8                      TINYINT UNSIGNED
-/// u16                     SMALLINT UNSIGNED
-/// u32                     INT UNSIGNED
-/// u64                     BIGINT UNSIGNED
-/// f32                     FLOAT
-/// f64                     DOUBLE
-/// &str, String            VARCHAR, CHAR, TEXT
-/// &[u8], Vec<u8>          VARBINARY, BINARY, BLOB
-///
-/// time::PrimitiveDateTime DATETIME
-/// time::OffsetDateTime    TIMESTAMP
-/// time::Date              DATE
-/// time::Time              TIME
-///
-/// bigdecimal::BigDecimal  DECIMAL
-///
-/// uuid::Uuid              BYTE(16), VARCHAR, CHAR, TEXT
-/// uuid::fmt::Hyphenated   CHAR(36)
-/// uuid::fmt::Simple       CHAR(32)
-///
-/// serde_json::JsonValue  JSON
-///
-/// Mysql 类型转换为Rust对应类型
-fn t2t(ty: &str) -> &str {
-    match ty.to_uppercase().as_str() {
-        "TINYINT(1)" | "BOOLEAN" => "bool",
-        "TINYINT" => "i8",
-        "TINYINT UNSIGNED" | "BIT" => "u8",
-        "SMALLINT" => "i16",
-        "SMALLINT UNSIGNED" => "u16",
-        "INT" | "MEDIUMINT" => "i32",
-        "INT UNSIGNED" | "MEDIUMINT UNSIGNED" => "u32",
-        "BIGINT" => "i64",
-        "BIGINT UNSIGNED" => "u64",
-        "FLOAT" => "f32",
-        "DOUBLE" | "NUMERIC" => "f64",
-        "VARBINARY" | "BINARY" | "BLOB" => "Vec<u8>",
-        "YEAR" => "time::Date",
-        "DATE" => "time::Date",
-        "TIME" => "time::Time",
-        "DATETIME" => "time::PrimitiveDateTime",
-        "TIMESTAMP" => "time::offsetDateTime",
-        "DECIMAL" => "bigdecimal::BigDecimal",
-        "JSON" => "serde_json:JsonValue",
-        _ => "String",
+    /// 优化器是否可见索引。
+    /// MariaDB 由 IGNORED 取反，MySQL 5.7 没有这一列，为 YES
+    is_visible: String,
+    /// MySQL 8.0.13 及更高版本支持功能键部分（请参阅功能键部分），
+    /// 这对 COLUMN_NAME 和 EXPRESSION 列都有影响：
+    /// 对于非功能键部分，COLUMN_NAME 表示由键部分索引的列，EXPRESSION 为 NULL。
+    /// 对于功能键部分，COLUMN_NAME 列为空，EXPRESSION 表示键部分的表达式。
+    expression: Option<String>,
+}
+
+/// `column` 属于 `schemas` 中的数据库，为空时为连接的默认数据库。
+/// 占位符由 `schema_query` 按顺序绑定
+fn schema_filter(column: &str, schemas: &[String]) -> String {
+    if schemas.is_empty() {
+        format!("{column} = ( SELECT DATABASE ())")
+    } else {
+        format!("{column} IN ({})", vec!["?"; schemas.len()].join(", "))
     }
 }
 
-async fn tables(url: &str) -> DResult<Vec<Table>> {
-    let sql = "SELECT table_schema,table_name,table_type,engine,table_comment
-         FROM information_schema.`TABLES` WHERE TABLE_SCHEMA = ( SELECT DATABASE ())";
+fn schema_query<'q>(sql: &'q str, schemas: &'q [String]) -> Query<'q, MySql, MySqlArguments> {
+    schemas.iter().fold(sqlx::query(sql), |q, s| q.bind(s))
+}
 
-    let pool = crate::conn_mysql(url).await?;
+/// 表名的 glob 规则转换为 LIKE 条件，转换为二进制比较以区分大小写
+fn table_filter(column: &str, filter: &Filter) -> (String, Vec<String>) {
+    filter.table_condition(&format!("CAST({column} AS BINARY)"), |_| "?".to_string())
+}
 
-    let res = sqlx::query(&sql)
-        .map(|row: MySqlRow| {
-            let table_schema: String = row.try_get(0).unwrap();
-            let table_name: String = row.try_get(1).unwrap();
-            let table_type: String = row.try_get(2).unwrap();
-            let engine: Option<String> = row.try_get(3).unwrap();
-            let table_comment: Option<String> = row.try_get(4).unwrap();
+/// 在 `schema_query` 之后按顺序绑定 `table_filter` 的模式
+fn table_query<'q>(
+    sql: &'q str,
+    schemas: &'q [String],
+    patterns: &'q [String],
+) -> Query<'q, MySql, MySqlArguments> {
+    patterns
+        .iter()
+        .fold(schema_query(sql, schemas), |q, p| q.bind(p))
+}
 
-            Table {
-                table_schema,
-                table_name,
-                table_type,
-                engine,
-                table_comment,
-            }
-        })
-        .fetch_all(&pool)
+/// 执行 information_schema 查询，按列名解码每行，解码失败的错误中包含列名、表名和 SQL
+async fn records<T>(
+    query: Query<'_, MySql, MySqlArguments>,
+    pool: &MySqlPool,
+    sql: &str,
+    decode: fn(&Record) -> DResult<T>,
+) -> DResult<Vec<T>> {
+    let rows = query
+        .fetch_all(pool)
         .await
-        .map_err(|e| {
-            dbg!(&e);
-            "SQL ERROR"
-        })?;
-    Ok(res)
+        .map_err(|e| Error::from(e).with_sql(sql))?;
+    rows.iter()
+        .map(|row| decode(&Record::from_mysql(row)?))
+        .collect::<DResult<_>>()
+        .map_err(|e| e.with_sql(sql))
+}
+
+async fn tables(pool: &MySqlPool, schemas: &[String], filter: &Filter) -> DResult<Vec<Table>> {
+    let (tables, patterns) = table_filter("table_name", filter);
+    let schema = schema_filter("table_schema", schemas);
+    let sql = format!(
+        "SELECT table_schema,table_name,table_type,engine,table_comment,
+            table_rows,data_length,index_length,data_free,auto_increment,
+            CAST(create_time AS CHAR) AS create_time,CAST(update_time AS CHAR) AS update_time,
+            table_collation,
+            ( SELECT character_set_name FROM information_schema.COLLATIONS c
+              WHERE c.collation_name = t.table_collation ) AS character_set
+         FROM information_schema.`TABLES` t WHERE {schema}{tables} AND table_type <> 'VIEW'
+         ORDER BY table_schema, table_name"
+    );
+    records(
+        table_query(&sql, schemas, &patterns),
+        pool,
+        &sql,
+        table_record,
+    )
+    .await
+}
+
+fn table_record(r: &Record) -> DResult<Table> {
+    Ok(Table {
+        table_schema: r.text("table_schema")?,
+        table_name: r.text("table_name")?,
+        table_type: r.text("table_type")?,
+        engine: r.str("engine")?,
+        table_rows: r.int("table_rows")?,
+        data_length: r.int("data_length")?,
+        index_length: r.int("index_length")?,
+        data_free: r.int("data_free")?,
+        auto_increment: r.int("auto_increment")?,
+        create_time: r.str("create_time")?,
+        update_time: r.str("update_time")?,
+        table_collation: r.str("table_collation")?,
+        table_comment: r.str("table_comment")?,
+        character_set: r.str("character_set")?,
+    })
+}
+
+/// 查询所有列，GENERATION_EXPRESSION 等列在旧版本中不存在
+async fn columns(
+    pool: &MySqlPool,
+    schemas: &[String],
+    filter: &Filter,
+) -> DResult<Vec<TableColumn>> {
+    let (tables, patterns) = table_filter("table_name", filter);
+    let schema = schema_filter("table_schema", schemas);
+    let sql = format!(
+        "SELECT * FROM information_schema.COLUMNS WHERE {schema}{tables}
+         ORDER BY table_schema, table_name, ordinal_position"
+    );
+    records(
+        table_query(&sql, schemas, &patterns),
+        pool,
+        &sql,
+        column_record,
+    )
+    .await
 }
 
-async fn columns(url: &str) -> DResult<Vec<TableColumn>> {
-    let sql = r#"SELECT table_schema,table_name,column_name,ordinal_position,column_default,is_nullable,data_type,
-            character_maximum_length,character_octet_length,numeric_precision,numeric_scale,datetime_precision,character_set_name,
-            column_type,column_key,privileges,column_comment 
-            FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = ( SELECT DATABASE ())"#
-        .to_string();
-    let pool = crate::conn_mysql(url).await?;
-    let res = sqlx::query(&sql)
-        .map(|row: MySqlRow| {
-            let table_schema: String = row.try_get(0).unwrap();
-            let table_name: String = row.try_get(1).unwrap();
-            let column_name: Option<String> = row.try_get(2).unwrap();
-            let ordinal_position: u32 = row.try_get(3).unwrap();
-            let column_default: Option<String> = row.try_get(4).unwrap();
-            let is_nullable: String = row.try_get(5).unwrap();
-            let data_type: Option<String> = row.try_get(6).unwrap();
-            let character_maximum_length: Option<i64> = row.try_get(7).unwrap();
-            let character_octet_length: Option<i64> = row.try_get(8).unwrap();
-            let numeric_precision: Option<u64> = row.try_get(9).unwrap();
-            let numeric_scale: Option<u64> = row.try_get(10).unwrap();
-            let datetime_precision: Option<u32> = row.try_get(11).unwrap();
-            let character_set_name: Option<String> = row.try_get(12).unwrap();
-            let column_type: String = row.try_get(13).unwrap();
-            let column_key: String = row.try_get(14).unwrap();
-            let privileges: Option<String> = row.try_get(15).unwrap();
-            let column_comment: String = row.try_get(16).unwrap();
-
-            TableColumn {
-                table_schema,
-                table_name,
-                column_name,
-                ordinal_position,
-                column_default,
-                is_nullable,
-                data_type,
-                character_maximum_length,
-                character_octet_length,
-                numeric_precision,
-                numeric_scale,
-                datetime_precision,
-                character_set_name,
-                column_type,
-                column_key,
-                privileges,
-                column_comment,
-            }
+fn column_record(r: &Record) -> DResult<TableColumn> {
+    Ok(TableColumn {
+        table_schema: r.text("table_schema")?,
+        table_name: r.text("table_name")?,
+        column_name: r.str("column_name")?,
+        ordinal_position: r.num("ordinal_position")?,
+        column_default: r.str("column_default")?,
+        is_nullable: r.text("is_nullable")?,
+        data_type: r.str("data_type")?,
+        character_maximum_length: r.int("character_maximum_length")?,
+        character_octet_length: r.int("character_octet_length")?,
+        numeric_precision: r.int("numeric_precision")?,
+        numeric_scale: r.int("numeric_scale")?,
+        datetime_precision: r.int("datetime_precision")?,
+        character_set_name: r.str("character_set_name")?,
+        collation_name: r.str("collation_name")?,
+        column_type: r.text("column_type")?,
+        column_key: r.str("column_key")?.unwrap_or_default(),
+        extra: r.str("extra")?,
+        privileges: r.str("privileges")?,
+        column_comment: r.str("column_comment")?.unwrap_or_default(),
+        generation_expression: r.str("generation_expression")?,
+    })
+}
+
+/// 查询所有列，IS_VISIBLE、EXPRESSION 在 MySQL 8.0 之前和 MariaDB 中不存在
+async fn indexs(pool: &MySqlPool, schemas: &[String], filter: &Filter) -> DResult<Vec<TableIndex>> {
+    let (tables, patterns) = table_filter("table_name", filter);
+    let schema = schema_filter("table_schema", schemas);
+    let sql = format!(
+        "SELECT * FROM information_schema.STATISTICS WHERE {schema}{tables}
+         ORDER BY table_schema, table_name, index_name = 'PRIMARY' DESC, index_name, seq_in_index"
+    );
+    records(
+        table_query(&sql, schemas, &patterns),
+        pool,
+        &sql,
+        index_record,
+    )
+    .await
+}
+
+fn index_record(r: &Record) -> DResult<TableIndex> {
+    // MariaDB 10.6 用 IGNORED 表示优化器不使用的索引
+    let is_visible = match (r.str("is_visible")?, r.str("ignored")?) {
+        (Some(visible), _) => visible,
+        (None, Some(ignored)) if ignored.eq_ignore_ascii_case("YES") => "NO".to_string(),
+        _ => "YES".to_string(),
+    };
+    Ok(TableIndex {
+        table_schema: r.text("table_schema")?,
+        table_name: r.text("table_name")?,
+        non_unique: r.num("non_unique")?,
+        index_name: r.str("index_name")?,
+        seq_in_index: r.num("seq_in_index")?,
+        column_name: r.str("column_name")?,
+        collation: r.str("collation")?,
+        cardinality: r.int("cardinality")?,
+        sub_part: r.int("sub_part")?,
+        nullable: r.str("nullable")?.unwrap_or_default(),
+        index_type: r.text("index_type")?,
+        comment: r.str("comment")?.unwrap_or_default(),
+        index_comment: r.str("index_comment")?.unwrap_or_default(),
+        is_visible,
+        expression: r.str("expression")?,
+    })
+}
+
+/// 每行只包含外键的一对列，由 `introspect::assemble` 合并
+async fn foreign_keys(
+    pool: &MySqlPool,
+    schemas: &[String],
+    filter: &Filter,
+) -> DResult<Vec<super::ForeignKey>> {
+    let (tables, patterns) = table_filter("k.table_name", filter);
+    let schema = schema_filter("k.table_schema", schemas);
+    let sql = format!(
+        "SELECT k.table_schema, k.table_name, k.constraint_name, k.column_name,
+            k.referenced_table_schema, k.referenced_table_name, k.referenced_column_name,
+            r.update_rule, r.delete_rule
+         FROM information_schema.KEY_COLUMN_USAGE k
+         JOIN information_schema.REFERENTIAL_CONSTRAINTS r
+            ON r.constraint_schema = k.constraint_schema AND r.table_name = k.table_name
+            AND r.constraint_name = k.constraint_name
+         WHERE {schema}{tables} AND k.referenced_table_name IS NOT NULL
+         ORDER BY k.table_schema, k.table_name, k.constraint_name, k.ordinal_position"
+    );
+    records(
+        table_query(&sql, schemas, &patterns),
+        pool,
+        &sql,
+        foreign_key_record,
+    )
+    .await
+}
+
+fn foreign_key_record(r: &Record) -> DResult<super::ForeignKey> {
+    Ok(super::ForeignKey {
+        table_schema: r.text("table_schema")?,
+        table_name: r.text("table_name")?,
+        constraint_name: r.text("constraint_name")?,
+        columns: vec![r.text("column_name")?],
+        referenced_schema: r.text("referenced_table_schema")?,
+        referenced_table: r.text("referenced_table_name")?,
+        referenced_columns: vec![r.text("referenced_column_name")?],
+        update_rule: r.text("update_rule")?,
+        delete_rule: r.text("delete_rule")?,
+        deferrable: false,
+        initially_deferred: false,
+    })
+}
+
+/// 每行只包含一个分区和最多一个子分区，由 `introspect::assemble` 合并。
+/// 不是分区表的表也有一行，partition_name 为 NULL
+async fn partitions(
+    pool: &MySqlPool,
+    schemas: &[String],
+    filter: &Filter,
+) -> DResult<Vec<super::Partitioning>> {
+    let (tables, patterns) = table_filter("table_name", filter);
+    let schema = schema_filter("table_schema", schemas);
+    let sql = format!(
+        "SELECT table_schema, table_name, partition_method, partition_expression,
+            subpartition_method, subpartition_expression, partition_name,
+            partition_ordinal_position, partition_description, subpartition_name,
+            subpartition_ordinal_position, table_rows, partition_comment
+         FROM information_schema.PARTITIONS
+         WHERE {schema}{tables} AND partition_name IS NOT NULL
+         ORDER BY table_schema, table_name, partition_ordinal_position,
+            subpartition_ordinal_position"
+    );
+    records(
+        table_query(&sql, schemas, &patterns),
+        pool,
+        &sql,
+        partition_record,
+    )
+    .await
+}
+
+fn partition_record(r: &Record) -> DResult<super::Partitioning> {
+    let method = r.text("partition_method")?;
+    let table_rows = r.int("table_rows")?;
+    let subpartition = r
+        .str("subpartition_name")?
+        .map(|name| -> DResult<_> {
+            Ok(super::Partition {
+                partition_name: name,
+                ordinal_position: r.int("subpartition_ordinal_position")?.unwrap_or_default(),
+                bound: None,
+                table_rows,
+                comment: None,
+                subpartitions: Vec::new(),
+            })
         })
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| {
-            dbg!(&e);
-            "SQL ERROR"
-        })?;
-    Ok(res)
+        .transpose()?;
+    Ok(super::Partitioning {
+        table_schema: r.text("table_schema")?,
+        table_name: r.text("table_name")?,
+        expression: r.str("partition_expression")?.filter(|e| !e.is_empty()),
+        subpartition_method: r.str("subpartition_method")?,
+        subpartition_expression: r.str("subpartition_expression")?,
+        partitions: vec![super::Partition {
+            partition_name: r.text("partition_name")?,
+            ordinal_position: r.int("partition_ordinal_position")?.unwrap_or_default(),
+            bound: r
+                .str("partition_description")?
+                .map(|d| partition_bound(&method, &d)),
+            table_rows,
+            comment: r.str("partition_comment")?.filter(|c| !c.is_empty()),
+            subpartitions: subpartition.into_iter().collect(),
+        }],
+        method,
+    })
 }
 
-async fn indexs(url: &str) -> DResult<Vec<TableIndex>> {
-    let sql = "SELECT table_schema,table_name,non_unique,index_name,seq_in_index,
-         column_name,collation,nullable,index_type,comment,index_comment
-         FROM information_schema.STATISTICS WHERE TABLE_SCHEMA = ( SELECT DATABASE ())"
-        .to_string();
-
-    let pool = crate::conn_mysql(url).await?;
-
-    let res = sqlx::query(&sql)
-        .map(|row: MySqlRow| {
-            let table_schema: String = row.try_get(0).unwrap();
-            let table_name: String = row.try_get(1).unwrap();
-            let non_unique: i32 = row.try_get(2).unwrap();
-            let index_name: Option<String> = row.try_get(3).unwrap();
-            let seq_in_index: u32 = row.try_get(4).unwrap();
-            let column_name: Option<String> = row.try_get(5).unwrap();
-            let collation: Option<String> = row.try_get(6).unwrap();
-            let nullable: String = row.try_get(7).unwrap();
-            let index_type: String = row.try_get(8).unwrap();
-            let comment: String = row.try_get(9).unwrap();
-            let index_comment: String = row.try_get(10).unwrap();
-
-            TableIndex {
-                table_schema,
-                table_name,
-                non_unique,
-                index_name,
-                seq_in_index,
-                column_name,
-                collation,
-                nullable,
-                index_type,
-                comment,
-                index_comment,
-            }
+/// 由 PARTITION_DESCRIPTION 还原建表语句中的分区范围
+fn partition_bound(method: &str, description: &str) -> String {
+    if method.starts_with("LIST") {
+        format!("VALUES IN ({description})")
+    } else if description == "MAXVALUE" {
+        "VALUES LESS THAN MAXVALUE".to_string()
+    } else {
+        format!("VALUES LESS THAN ({description})")
+    }
+}
+
+/// CHECK 约束，MySQL 8.0.16 之前没有 CHECK_CONSTRAINTS，返回空。
+/// MariaDB 的列级约束以列名命名，不同表中可以同名，需要按 CHECK_CONSTRAINTS.TABLE_NAME 关联；
+/// 没有这一列的版本约束名在数据库中唯一，只按约束名关联。
+/// MariaDB 的 TABLE_CONSTRAINTS 没有 ENFORCED，约束总是生效
+async fn check_constraints(
+    pool: &MySqlPool,
+    schemas: &[String],
+    filter: &Filter,
+) -> DResult<Vec<super::TableConstraint>> {
+    let (tables, patterns) = table_filter("t.table_name", filter);
+    let schema = schema_filter("t.table_schema", schemas);
+    let sql = |on_table: &str| {
+        format!(
+            "SELECT t.*, c.check_clause
+             FROM information_schema.TABLE_CONSTRAINTS t
+             JOIN information_schema.CHECK_CONSTRAINTS c
+                ON c.constraint_schema = t.constraint_schema{on_table}
+                AND c.constraint_name = t.constraint_name
+             WHERE {schema}{tables} AND t.constraint_type = 'CHECK'
+             ORDER BY t.table_schema, t.table_name, t.constraint_name"
+        )
+    };
+
+    let sql_on_table = sql(" AND c.table_name = t.table_name");
+    let query = table_query(&sql_on_table, schemas, &patterns);
+    let res = match records(query, pool, &sql_on_table, check_record).await {
+        // ER_BAD_FIELD_ERROR：CHECK_CONSTRAINTS 没有 TABLE_NAME
+        Err(e) if e.sqlstate.as_deref() == Some("42S22") => {
+            let sql = sql("");
+            records(
+                table_query(&sql, schemas, &patterns),
+                pool,
+                &sql,
+                check_record,
+            )
+            .await
+        }
+        res => res,
+    };
+    match res {
+        // ER_UNKNOWN_TABLE, ER_NO_SUCH_TABLE
+        Err(e) if e.sqlstate.as_deref() == Some("42S02") => Ok(Vec::new()),
+        res => res,
+    }
+}
+
+fn check_record(r: &Record) -> DResult<super::TableConstraint> {
+    let clause = r.text("check_clause")?;
+    let mut definition = if clause.starts_with('(') {
+        format!("CHECK {clause}")
+    } else {
+        format!("CHECK ({clause})")
+    };
+    if r.str("enforced")?.as_deref() == Some("NO") {
+        definition.push_str(" NOT ENFORCED");
+    }
+    Ok(super::TableConstraint {
+        table_schema: r.text("table_schema")?,
+        table_name: r.text("table_name")?,
+        constraint_name: r.text("constraint_name")?,
+        constraint_type: "CHECK".to_string(),
+        definition,
+    })
+}
+
+/// 服务器上的数据库，不包含系统数据库。大小为各表数据和索引的估计值之和，
+/// 有表的过滤规则时按规则重新统计表数量
+pub async fn schemas(pool: &MySqlPool, filter: &Filter) -> DResult<Vec<super::Schema>> {
+    let sql = "SELECT s.schema_name, s.default_character_set_name, s.default_collation_name,
+            COUNT(t.table_name) AS table_count, SUM(t.data_length + t.index_length) AS size
+         FROM information_schema.SCHEMATA s
+         LEFT JOIN information_schema.`TABLES` t
+            ON t.table_schema = s.schema_name AND t.table_type = 'BASE TABLE'
+         WHERE s.schema_name NOT IN ('information_schema', 'mysql', 'performance_schema', 'sys')
+         GROUP BY s.schema_name, s.default_character_set_name, s.default_collation_name
+         ORDER BY s.schema_name";
+    let mut res = records(sqlx::query(sql), pool, sql, schema_record).await?;
+    if filter.has_rules(ObjectKind::Table) {
+        let (tables, patterns) = table_filter("table_name", filter);
+        let sql = format!(
+            "SELECT table_schema, table_name FROM information_schema.`TABLES`
+             WHERE table_type = 'BASE TABLE'{tables}"
+        );
+        let names = records(table_query(&sql, &[], &patterns), pool, &sql, |r| {
+            Ok((r.text("table_schema")?, r.text("table_name")?))
         })
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| {
-            dbg!(&e);
-            "SQL ERROR"
-        })?;
+        .await?;
+        introspect::count_tables(&mut res, &names, filter);
+    }
+    Ok(res)
+}
+
+fn schema_record(r: &Record) -> DResult<super::Schema> {
+    Ok(super::Schema {
+        schema_name: r.text("schema_name")?,
+        character_set: r.str("default_character_set_name")?,
+        collation: r.str("default_collation_name")?,
+        table_count: r.num("table_count")?,
+        size: r.int("size")?,
+    })
+}
+
+pub async fn views(pool: &MySqlPool, schemas: &[String]) -> DResult<Vec<super::View>> {
+    let schema = schema_filter("table_schema", schemas);
+    let sql = format!(
+        "SELECT table_schema, table_name, view_definition, check_option, definer, security_type
+         FROM information_schema.VIEWS WHERE {schema}
+         ORDER BY table_schema, table_name"
+    );
+    records(schema_query(&sql, schemas), pool, &sql, view_record).await
+}
+
+fn view_record(r: &Record) -> DResult<super::View> {
+    Ok(super::View {
+        view_schema: r.text("table_schema")?,
+        view_name: r.text("table_name")?,
+        definition: r.str("view_definition")?.unwrap_or_default(),
+        check_option: r.str("check_option")?.filter(|c| c != "NONE"),
+        materialized: false,
+        definer: r.str("definer")?,
+        security_type: r.str("security_type")?,
+    })
+}
+
+pub async fn triggers(pool: &MySqlPool, schemas: &[String]) -> DResult<Vec<super::Trigger>> {
+    let schema = schema_filter("trigger_schema", schemas);
+    let sql = format!(
+        "SELECT trigger_schema, trigger_name, event_object_table, action_timing,
+            event_manipulation, action_orientation, action_order, action_statement,
+            definer, sql_mode
+         FROM information_schema.TRIGGERS WHERE {schema}
+         ORDER BY trigger_schema, event_object_table, action_timing, event_manipulation,
+            action_order"
+    );
+    records(schema_query(&sql, schemas), pool, &sql, trigger_record).await
+}
+
+fn trigger_record(r: &Record) -> DResult<super::Trigger> {
+    Ok(super::Trigger {
+        trigger_schema: r.text("trigger_schema")?,
+        trigger_name: r.text("trigger_name")?,
+        table_name: r.text("event_object_table")?,
+        timing: r.text("action_timing")?,
+        events: vec![r.text("event_manipulation")?],
+        orientation: r.text("action_orientation")?,
+        action_order: r.int("action_order")?,
+        definition: r.text("action_statement")?,
+        definer: r.str("definer")?,
+        sql_mode: r.str("sql_mode")?,
+    })
+}
+
+/// 存储过程和函数的参数，按 (specific_schema, specific_name) 分组。
+/// 函数返回值的 ordinal_position 为 0，不包含在内
+async fn parameters(
+    pool: &MySqlPool,
+    schemas: &[String],
+) -> DResult<HashMap<(String, String), Vec<super::RoutineParameter>>> {
+    let schema = schema_filter("specific_schema", schemas);
+    let sql = format!(
+        "SELECT specific_schema, specific_name, ordinal_position,
+            parameter_mode, parameter_name, dtd_identifier
+         FROM information_schema.PARAMETERS
+         WHERE {schema} AND ordinal_position > 0
+         ORDER BY specific_schema, specific_name, ordinal_position"
+    );
+    let rows = records(schema_query(&sql, schemas), pool, &sql, parameter_record).await?;
+
+    let mut res: HashMap<(String, String), Vec<super::RoutineParameter>> = HashMap::new();
+    for (key, parameter) in rows {
+        res.entry(key).or_default().push(parameter);
+    }
     Ok(res)
 }
 
-pub async fn table(url: &str) -> DResult<Vec<super::Table>> {
-    let tables = tables(url).await?;
-    if tables.is_empty() {
-        return Ok(Vec::with_capacity(0));
+fn parameter_record(r: &Record) -> DResult<((String, String), super::RoutineParameter)> {
+    Ok((
+        (r.text("specific_schema")?, r.text("specific_name")?),
+        super::RoutineParameter {
+            ordinal_position: r.num("ordinal_position")?,
+            // 函数的参数没有 parameter_mode
+            mode: r.str("parameter_mode")?.unwrap_or_else(|| "IN".to_string()),
+            name: r.str("parameter_name")?,
+            data_type: r.text("dtd_identifier")?,
+        },
+    ))
+}
+
+pub async fn routines(pool: &MySqlPool, schemas: &[String]) -> DResult<Vec<super::Routine>> {
+    let schema = schema_filter("routine_schema", schemas);
+    let sql = format!(
+        "SELECT routine_schema, routine_name, specific_name, routine_type, dtd_identifier,
+            routine_body, routine_definition, is_deterministic, sql_data_access, definer,
+            security_type, sql_mode, routine_comment
+         FROM information_schema.ROUTINES WHERE {schema}
+         ORDER BY routine_schema, routine_type, routine_name"
+    );
+    let routines = records(schema_query(&sql, schemas), pool, &sql, routine_record);
+    let (routines, mut parameters) = tokio::try_join!(routines, parameters(pool, schemas))?;
+    Ok(routines
+        .into_iter()
+        .map(|mut r| {
+            let key = (r.routine_schema.clone(), r.specific_name.clone());
+            r.parameters = parameters.remove(&key).unwrap_or_default();
+            r
+        })
+        .collect())
+}
+
+fn routine_record(r: &Record) -> DResult<super::Routine> {
+    Ok(super::Routine {
+        routine_schema: r.text("routine_schema")?,
+        routine_name: r.text("routine_name")?,
+        specific_name: r.text("specific_name")?,
+        routine_type: r.text("routine_type")?,
+        parameters: Vec::new(),
+        return_type: r.str("dtd_identifier")?,
+        language: r.text("routine_body")?,
+        definition: r.str("routine_definition")?,
+        deterministic: r.str("is_deterministic")?.as_deref() == Some("YES"),
+        data_access: r.str("sql_data_access")?,
+        definer: r.str("definer")?,
+        security_type: r.text("security_type")?,
+        sql_mode: r.str("sql_mode")?,
+        comment: r.str("routine_comment")?,
+    })
+}
+
+pub async fn events(pool: &MySqlPool, schemas: &[String]) -> DResult<Vec<super::Event>> {
+    let schema = schema_filter("event_schema", schemas);
+    let sql = format!(
+        "SELECT event_schema, event_name, event_definition, event_type,
+            CAST(execute_at AS CHAR) AS execute_at,
+            CONCAT(interval_value, ' ', interval_field) AS event_interval,
+            CAST(starts AS CHAR) AS starts, CAST(ends AS CHAR) AS ends, status, on_completion,
+            definer, time_zone, sql_mode, event_comment
+         FROM information_schema.EVENTS WHERE {schema}
+         ORDER BY event_schema, event_name"
+    );
+    records(schema_query(&sql, schemas), pool, &sql, event_record).await
+}
+
+fn event_record(r: &Record) -> DResult<super::Event> {
+    Ok(super::Event {
+        event_schema: r.text("event_schema")?,
+        event_name: r.text("event_name")?,
+        definition: r.text("event_definition")?,
+        event_type: r.text("event_type")?,
+        execute_at: r.str("execute_at")?,
+        interval: r.str("event_interval")?,
+        starts: r.str("starts")?,
+        ends: r.str("ends")?,
+        status: r.text("status")?,
+        on_completion: r.text("on_completion")?,
+        definer: r.text("definer")?,
+        time_zone: r.text("time_zone")?,
+        sql_mode: r.str("sql_mode")?.unwrap_or_default(),
+        comment: r.str("event_comment")?.unwrap_or_default(),
+    })
+}
+
+impl From<Table> for super::Table {
+    fn from(t: Table) -> Self {
+        Self {
+            table_schema: t.table_schema,
+            table_name: t.table_name,
+            table_type: t.table_type,
+            engine: t.engine,
+            table_comment: t.table_comment,
+            character_set: t.character_set,
+            table_collation: t.table_collation,
+            create_options: None,
+            fileds: Vec::new(),
+            indexs: Vec::new(),
+            constraints: Vec::new(),
+            foreign_keys: Vec::new(),
+            partitioning: None,
+            stats: super::TableStats {
+                table_rows: t.table_rows,
+                data_length: t.data_length,
+                index_length: t.index_length,
+                data_free: t.data_free,
+                auto_increment: t.auto_increment,
+                create_time: t.create_time,
+                update_time: t.update_time,
+            },
+        }
+    }
+}
+
+impl From<TableColumn> for super::TableColumn {
+    fn from(c: TableColumn) -> Self {
+        let extra = c.extra.as_deref().unwrap_or_default();
+        let parsed_type = coltype::parse(&c.column_type, Driver::Mysql);
+        Self {
+            table_schema: c.table_schema,
+            table_name: c.table_name,
+            column_name: c.column_name,
+            ordinal_position: c.ordinal_position,
+            column_default: c.column_default,
+            data_type: c.data_type,
+            character_maximum_length: c.character_maximum_length,
+            character_octet_length: c.character_octet_length,
+            numeric_precision: c.numeric_precision,
+            numeric_scale: c.numeric_scale,
+            datetime_precision: c.datetime_precision,
+            character_set_name: c.character_set_name,
+            collation_name: c.collation_name,
+            // 需要表的排序规则，在 `table` 中设置
+            explicit_collation: false,
+            rust_type: coltype::rust_type(&parsed_type, Driver::Mysql, c.is_nullable == "YES"),
+            is_nullable: c.is_nullable,
+            parsed_type,
+            column_type: c.column_type,
+            column_key: c.column_key,
+            privileges: c.privileges,
+            column_comment: c.column_comment,
+            identity: None,
+            sequence: None,
+            generated: extra_flag(extra, "VIRTUAL GENERATED")
+                .then(|| "VIRTUAL".to_string())
+                .or_else(|| extra_flag(extra, "STORED GENERATED").then(|| "STORED".to_string())),
+            generation_expression: c.generation_expression.filter(|e| !e.is_empty()),
+            auto_increment: extra_flag(extra, "auto_increment"),
+            on_update: on_update(extra),
+            default_generated: extra_flag(extra, "DEFAULT_GENERATED"),
+            extra: c.extra.filter(|e| !e.is_empty()),
+        }
+    }
+}
+
+/// EXTRA 中是否包含 `flag`，不区分大小写
+fn extra_flag(extra: &str, flag: &str) -> bool {
+    extra
+        .to_ascii_lowercase()
+        .contains(&flag.to_ascii_lowercase())
+}
+
+/// EXTRA 中 `on update` 之后的表达式
+fn on_update(extra: &str) -> Option<String> {
+    let pos = extra.to_ascii_lowercase().find("on update ")?;
+    Some(extra[pos + "on update ".len()..].trim().to_string())
+}
+
+/// 每行只包含索引的一个键，由 `introspect::assemble` 合并
+impl From<TableIndex> for super::TableIndex {
+    fn from(i: TableIndex) -> Self {
+        Self {
+            table_schema: i.table_schema,
+            table_name: i.table_name,
+            non_unique: i.non_unique != 0,
+            index_name: i.index_name.unwrap_or_default(),
+            columns: vec![super::IndexPart {
+                seq_in_index: i.seq_in_index,
+                column_name: i.column_name,
+                expression: i.expression,
+                collation: i.collation,
+                sub_part: i.sub_part.and_then(|n| u32::try_from(n).ok()),
+                cardinality: i.cardinality.and_then(|n| u64::try_from(n).ok()),
+            }],
+            comment: i.comment,
+            index_comment: i.index_comment,
+            index_type: Some(i.index_type),
+            predicate: None,
+            visible: i.is_visible == "YES",
+        }
+    }
+}
+
+/// `schemas` 中各数据库的表，为空时为连接的默认数据库。列按 ordinal_position 排序，
+/// 多列索引、外键合并为一项。constraints 只包含 CHECK 约束，主键和唯一约束见 indexs。
+/// `filter` 中表名的 glob 规则在查询中检查，其他规则由调用方检查
+pub async fn table(
+    pool: &MySqlPool,
+    schemas: &[String],
+    filter: &Filter,
+) -> DResult<Vec<super::Table>> {
+    let (tables, columns, indexs, constraints, foreign_keys, partitionings) = tokio::try_join!(
+        tables(pool, schemas, filter),
+        columns(pool, schemas, filter),
+        indexs(pool, schemas, filter),
+        check_constraints(pool, schemas, filter),
+        foreign_keys(pool, schemas, filter),
+        partitions(pool, schemas, filter)
+    )?;
+
+    let mut tables = tables
+        .into_iter()
+        .map(super::Table::from)
+        .collect::<Vec<_>>();
+    let parts = introspect::TableParts {
+        columns: columns.into_iter().map(Into::into).collect(),
+        indexs: indexs.into_iter().map(Into::into).collect(),
+        constraints,
+        foreign_keys,
+        partitionings,
+    };
+    introspect::assemble(&mut tables, parts);
+    for table in &mut tables {
+        for column in &mut table.fileds {
+            column.explicit_collation =
+                column.collation_name.is_some() && column.collation_name != table.table_collation;
+        }
+    }
+    Ok(tables)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value as Json};
+
+    use super::*;
+    use crate::{error::ErrorCode, record::Value};
+
+    /// 读取 tests/fixtures/mysql 中记录的查询结果，每列为 [列名, 驱动报告的类型]
+    fn fixture(server: &str, query: &str) -> Json {
+        let path = format!(
+            "{}/tests/fixtures/mysql/{server}.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let fixture: Json = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        fixture[query].clone()
+    }
+
+    fn records(result: &Json) -> Vec<Record> {
+        let columns = result["columns"].as_array().unwrap();
+        result["rows"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| {
+                Record::new(columns.iter().zip(row.as_array().unwrap()).map(|(c, v)| {
+                    let ty = c[1].as_str().unwrap();
+                    let value = match v {
+                        Json::Null => Value::Null,
+                        Json::Number(n) if ty.ends_with("UNSIGNED") => {
+                            Value::UInt(n.as_u64().unwrap())
+                        }
+                        Json::Number(n) => Value::Int(n.as_i64().unwrap()),
+                        Json::String(s) if ty.contains("BINARY") || ty.contains("BLOB") => {
+                            Value::Bytes(s.clone().into_bytes())
+                        }
+                        Json::String(s) => Value::Text(s.clone()),
+                        v => panic!("unexpected fixture value {v}"),
+                    };
+                    (c[0].as_str().unwrap().to_string(), value)
+                }))
+            })
+            .collect()
+    }
+
+    fn decode<T>(server: &str, query: &str, f: fn(&Record) -> DResult<T>) -> Vec<T> {
+        records(&fixture(server, query))
+            .iter()
+            .map(|r| f(r).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn decode_server_versions() {
+        for server in ["mysql-5.7", "mysql-8.0", "mariadb-10.6"] {
+            let tables = decode(server, "tables", table_record);
+            assert_eq!(tables[0].table_name, "orders", "{server}");
+            assert_eq!(tables[0].auto_increment, Some(4), "{server}");
+            assert_eq!(
+                tables[0].create_time.as_deref(),
+                Some("2024-05-01 10:00:00")
+            );
+
+            let columns = decode(server, "columns", column_record);
+            let positions = columns
+                .iter()
+                .map(|c| c.ordinal_position)
+                .collect::<Vec<_>>();
+            assert_eq!(positions, [1, 2], "{server}");
+            assert_eq!(
+                columns[0].extra.as_deref(),
+                Some("auto_increment"),
+                "{server}"
+            );
+            assert_eq!(columns[1].character_maximum_length, Some(20), "{server}");
+            assert_eq!(columns[1].column_comment, "备注", "{server}");
+
+            let indexs = decode(server, "statistics", index_record);
+            assert_eq!(indexs[0].non_unique, 0, "{server}");
+            assert_eq!(indexs[1].seq_in_index, 1, "{server}");
+            assert_eq!(indexs[1].sub_part, Some(10), "{server}");
+            assert_eq!(indexs[1].index_type, "BTREE", "{server}");
+        }
+
+        // 没有 IS_VISIBLE 的版本
+        let visible = |server| {
+            decode(server, "statistics", index_record)
+                .into_iter()
+                .map(|i| i.is_visible)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(visible("mysql-5.7"), ["YES", "YES"]);
+        assert_eq!(visible("mariadb-10.6"), ["YES", "NO"]);
+        let indexs = decode("mysql-8.0", "statistics", index_record);
+        assert_eq!(indexs[1].is_visible, "NO");
+        assert_eq!(indexs[2].expression.as_deref(), Some("lower(`note`)"));
+    }
+
+    #[test]
+    fn decode_other_objects() {
+        for server in ["mysql-5.7", "mysql-8.0", "mariadb-10.6"] {
+            let schemas = decode(server, "schemas", schema_record);
+            assert_eq!((schemas[0].table_count, schemas[0].size), (2, Some(65536)));
+
+            let foreign_keys = decode(server, "foreign_keys", foreign_key_record);
+            assert_eq!(foreign_keys[0].referenced_columns, ["id"], "{server}");
+            assert_eq!(foreign_keys[0].delete_rule, "CASCADE", "{server}");
+
+            let partitionings = decode(server, "partitions", partition_record);
+            let bounds = partitionings
+                .iter()
+                .map(|p| {
+                    (
+                        p.partitions[0].ordinal_position,
+                        p.partitions[0].bound.clone(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                bounds,
+                [
+                    (1, Some("VALUES LESS THAN (2025)".to_string())),
+                    (2, Some("VALUES LESS THAN MAXVALUE".to_string())),
+                ],
+                "{server}"
+            );
+            assert_eq!(partitionings[0].expression.as_deref(), Some("year(`at`)"));
+
+            let views = decode(server, "views", view_record);
+            assert_eq!(
+                views[0].check_option.as_deref(),
+                Some("CASCADED"),
+                "{server}"
+            );
+
+            let triggers = decode(server, "triggers", trigger_record);
+            assert_eq!(triggers[0].action_order, Some(1), "{server}");
+            assert!(triggers[0].sql_mode.is_some(), "{server}");
+
+            let parameters = decode(server, "parameters", parameter_record);
+            let modes = parameters
+                .iter()
+                .map(|(_, p)| (p.ordinal_position, p.mode.as_str()))
+                .collect::<Vec<_>>();
+            assert_eq!(modes, [(1, "IN"), (1, "IN"), (2, "OUT")], "{server}");
+
+            let routines = decode(server, "routines", routine_record);
+            assert!(routines[0].deterministic && !routines[1].deterministic);
+            assert_eq!(routines[1].return_type, None, "{server}");
+            assert_eq!(routines[1].comment.as_deref(), Some("下单"), "{server}");
+
+            let events = decode(server, "events", event_record);
+            assert_eq!(events[0].interval.as_deref(), Some("1 DAY"), "{server}");
+            assert_eq!(events[0].ends, None, "{server}");
+        }
+    }
+
+    #[test]
+    fn decode_check_constraints() {
+        // MariaDB 的列级约束在两个表中同名，没有 ENFORCED
+        let checks = decode("mariadb-10.6", "check_constraints", check_record);
+        let checks = checks
+            .iter()
+            .map(|c| {
+                (
+                    c.table_name.as_str(),
+                    c.constraint_name.as_str(),
+                    c.definition.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            checks,
+            [
+                ("items", "price", "CHECK (`price` > 0)"),
+                ("orders", "price", "CHECK (`price` >= 0)"),
+            ]
+        );
+
+        let checks = decode("mysql-8.0", "check_constraints", check_record);
+        assert_eq!(checks[0].definition, "CHECK (`price` >= 0)");
+        assert_eq!(
+            checks[1].definition,
+            "CHECK (`note` <> _utf8mb4'') NOT ENFORCED"
+        );
     }
-    let columns = columns(url).await?;
-    let indexs = indexs(url).await?;
 
-    unimplemented!()
+    #[test]
+    fn decode_errors() {
+        let error = |query: &str, column: usize, value: Json, f: fn(&Record) -> DResult<()>| {
+            let mut result = fixture("mysql-8.0", query);
+            result["rows"][0][column] = value;
+            f(&records(&result)[0]).unwrap_err()
+        };
+
+        // ORDINAL_POSITION 为 NULL
+        let err = error("columns", 4, Json::Null, |r| column_record(r).map(|_| ()));
+        assert_eq!(err.code, ErrorCode::Decode);
+        assert_eq!(err.message, "column ordinal_position: is NULL or missing");
+        assert_eq!(err.object.as_deref(), Some("shop.orders"));
+
+        // 超出 i32 的 NON_UNIQUE
+        let err = error("statistics", 3, json!(1_i64 << 40), |r| {
+            index_record(r).map(|_| ())
+        });
+        assert_eq!(
+            err.message,
+            "column non_unique: value 1099511627776 is out of range"
+        );
+
+        // 不是整数的 SEQ_IN_INDEX
+        let err = error("statistics", 6, json!("first"), |r| {
+            index_record(r).map(|_| ())
+        });
+        assert_eq!(
+            err.message,
+            r#"column seq_in_index: expected an integer, found Text("first")"#
+        );
+        assert_eq!(err.object.as_deref(), Some("shop.orders"));
+
+        // 触发器没有 table_name，错误中为触发器名
+        let err = error("triggers", 7, Json::Null, |r| trigger_record(r).map(|_| ()));
+        assert_eq!(err.message, "column action_statement: is NULL or missing");
+        assert_eq!(err.object.as_deref(), Some("shop.orders_bi"));
+
+        // 不是 UTF-8 的 VARBINARY，JSON 中无法记录
+        let record = Record::new([
+            ("TABLE_SCHEMA".to_string(), Value::Text("shop".to_string())),
+            ("TABLE_NAME".to_string(), Value::Bytes(b"orders".to_vec())),
+            ("INDEX_TYPE".to_string(), Value::Bytes(vec![0xff, 0xfe])),
+        ]);
+        let err = record.text("index_type").unwrap_err();
+        assert_eq!(err.message, "column index_type: is not valid UTF-8");
+        assert_eq!(err.object.as_deref(), Some("shop.orders"));
+    }
 }
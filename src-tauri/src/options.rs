@@ -0,0 +1,241 @@
+//! 连接参数
+//!
+//! 通过 sqlx 的 `ConnectOptions` 构造连接，避免拼接 URL 时用户名、密码中的
+//! `@`、`/`、`:`、`#` 等字符破坏 URL。
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    mysql::{MySqlConnectOptions, MySqlSslMode},
+    postgres::{PgConnectOptions, PgSslMode},
+    sqlite::SqliteConnectOptions,
+};
+
+use crate::{
+    error::{Error, ErrorCode},
+    DResult, DbConn,
+};
+
+/// 各数据库驱动的连接参数，只使用与 `DbConn.driver` 对应的部分
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DriverOptions {
+    pub mysql: MysqlOptions,
+    pub postgres: PgOptions,
+    pub sqlite: SqliteOptions,
+}
+
+/// TLS 模式，MySQL 的 verify-full 对应 VERIFY_IDENTITY
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    #[default]
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl From<SslMode> for MySqlSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disable => MySqlSslMode::Disabled,
+            SslMode::Prefer => MySqlSslMode::Preferred,
+            SslMode::Require => MySqlSslMode::Required,
+            SslMode::VerifyCa => MySqlSslMode::VerifyCa,
+            SslMode::VerifyFull => MySqlSslMode::VerifyIdentity,
+        }
+    }
+}
+
+impl From<SslMode> for PgSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+            SslMode::VerifyCa => PgSslMode::VerifyCa,
+            SslMode::VerifyFull => PgSslMode::VerifyFull,
+        }
+    }
+}
+
+/// MySQL、PostgreSQL 的 TLS 配置，证书均为 PEM 文件路径
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    pub mode: SslMode,
+    /// CA 证书，verify-ca、verify-full 时用于校验服务端证书
+    pub ca: Option<String>,
+    /// 客户端证书
+    pub cert: Option<String>,
+    /// 客户端私钥
+    pub key: Option<String>,
+}
+
+impl TlsConfig {
+    /// 检查证书文件是否存在，返回 (ca, cert, key)
+    fn files(&self) -> DResult<(Option<&str>, Option<&str>, Option<&str>)> {
+        let ca = existing_file(&self.ca, "TLS CA file not found")?;
+        let cert = existing_file(&self.cert, "TLS client certificate not found")?;
+        let key = existing_file(&self.key, "TLS client key not found")?;
+        if cert.is_some() != key.is_some() {
+            return Err(Error::new(
+                ErrorCode::Config,
+                "TLS client certificate and key must be set together",
+            ));
+        }
+        Ok((ca, cert, key))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MysqlOptions {
+    /// 连接字符集，如 utf8mb4
+    pub charset: Option<String>,
+    /// 连接排序规则，如 utf8mb4_general_ci
+    pub collation: Option<String>,
+    /// 会话时区，如 +08:00
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PgOptions {
+    /// 在 pg_stat_activity 中显示的应用名
+    pub application_name: Option<String>,
+    /// 模式搜索路径，如 "app, public"
+    pub search_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SqliteOptions {
+    /// 只读打开，相当于 mode=ro
+    pub read_only: bool,
+    /// 文件不存在时创建，相当于 mode=rwc
+    pub create_if_missing: bool,
+}
+
+/// 设置了路径时检查文件是否存在
+fn existing_file<'a>(path: &'a Option<String>, err: &'static str) -> DResult<Option<&'a str>> {
+    match non_empty(path) {
+        Some(p) if !Path::new(p).is_file() => {
+            Err(Error::new(ErrorCode::Config, err).with_object(p))
+        }
+        p => Ok(p),
+    }
+}
+
+/// 空字符串视为未设置
+fn non_empty(s: &Option<String>) -> Option<&str> {
+    s.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// PostgreSQL 启动包 `options` 中的值，服务端按空白拆分参数，空白和反斜杠需要用反斜杠转义
+fn startup_option(value: &str) -> String {
+    let mut res = String::new();
+    for c in value.chars() {
+        if c == '\\' || c.is_whitespace() {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res
+}
+
+impl DbConn {
+    pub fn mysql_options(&self) -> DResult<MySqlConnectOptions> {
+        let opts = &self.options.mysql;
+        let (ca, cert, key) = self.tls.files()?;
+        let mut options = MySqlConnectOptions::new()
+            .host(&self.host)
+            .port(self.port)
+            .username(&self.username)
+            .password(&self.password()?)
+            .ssl_mode(self.tls.mode.into());
+        if let Some(ca) = ca {
+            options = options.ssl_ca(ca);
+        }
+        if let (Some(cert), Some(key)) = (cert, key) {
+            options = options.ssl_client_cert(cert).ssl_client_key(key);
+        }
+        if !self.database.is_empty() {
+            options = options.database(&self.database);
+        }
+        if let Some(charset) = non_empty(&opts.charset) {
+            options = options.charset(charset);
+        }
+        if let Some(collation) = non_empty(&opts.collation) {
+            options = options.collation(collation);
+        }
+        if let Some(timezone) = non_empty(&opts.timezone) {
+            options = options.timezone(Some(timezone.to_string()));
+        }
+        Ok(options)
+    }
+
+    pub fn pg_options(&self) -> DResult<PgConnectOptions> {
+        let opts = &self.options.postgres;
+        let (ca, cert, key) = self.tls.files()?;
+        let mut options = PgConnectOptions::new()
+            .host(&self.host)
+            .port(self.port)
+            .username(&self.username)
+            .password(&self.password()?)
+            .ssl_mode(self.tls.mode.into());
+        if let Some(ca) = ca {
+            options = options.ssl_root_cert(ca);
+        }
+        if let (Some(cert), Some(key)) = (cert, key) {
+            options = options.ssl_client_cert(cert).ssl_client_key(key);
+        }
+        if !self.database.is_empty() {
+            options = options.database(&self.database);
+        }
+        if let Some(name) = non_empty(&opts.application_name) {
+            options = options.application_name(name);
+        }
+        if let Some(path) = non_empty(&opts.search_path) {
+            options = options.options([("search_path", startup_option(path))]);
+        }
+        if self.protected {
+            options = options.options([("default_transaction_read_only", "on")]);
+        }
+        Ok(options)
+    }
+
+    pub fn sqlite_options(&self) -> DResult<SqliteConnectOptions> {
+        let opts = &self.options.sqlite;
+        Ok(SqliteConnectOptions::new()
+            .filename(&self.database)
+            .read_only(opts.read_only || self.protected)
+            .create_if_missing(opts.create_if_missing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pg_search_path() {
+        let conn: DbConn = serde_json::from_value(serde_json::json!({
+            "driver": "postgres",
+            "name": "pg",
+            "host": "127.0.0.1",
+            "port": 5432,
+            "username": "postgres",
+            "password": "",
+            "database": "postgres",
+            "options": { "postgres": { "search_path": "app, public" } },
+        }))
+        .unwrap();
+        let options = conn.pg_options().unwrap();
+        assert_eq!(options.get_options(), Some("-c search_path=app,\\ public"));
+        assert_eq!(startup_option("a\\b c"), "a\\\\b\\ c");
+    }
+}
@@ -0,0 +1,747 @@
+//! PostgreSQL 表结构
+//!
+//! 基于 `pg_catalog` 和 `information_schema` 读取指定模式的表，
+//! 没有指定时为 `search_path` 中的模式。
+//! 分区表只返回父表，分区不单独列出，见 `Table.partitioning`。
+//! 使用了 `attgenerated`、`pg_partition_tree` 等，需要 PostgreSQL 12 及以上版本，
+//! 打开连接池时由 `check_version` 检查。
+
+use std::collections::HashMap;
+
+use sqlx::{
+    postgres::{PgArguments, PgRow},
+    query::Query,
+    PgPool, Postgres, Row,
+};
+
+use crate::{
+    coltype,
+    error::{Error, ErrorCode},
+    filter::{Filter, ObjectKind},
+    introspect, DResult, Driver, ForeignKey, IndexPart, Partition, Partitioning, Routine,
+    RoutineParameter, Schema, Sequence, Table, TableColumn, TableConstraint, TableIndex,
+    TableStats, Trigger, View,
+};
+
+/// 支持的最低版本，`server_version_num` 的格式
+const MIN_VERSION: i32 = 120000;
+
+/// 版本低于 `MIN_VERSION` 时返回 `ErrorCode::Unsupported`
+pub async fn check_version(pool: &PgPool) -> DResult<()> {
+    let sql =
+        "SELECT current_setting('server_version_num')::int4, current_setting('server_version')";
+    let (num, version): (i32, String) = sqlx::query_as(sql)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))?;
+    if num < MIN_VERSION {
+        return Err(Error::new(
+            ErrorCode::Unsupported,
+            format!("PostgreSQL {version} is not supported, version 12 or later is required"),
+        ));
+    }
+    Ok(())
+}
+
+/// 只读取 $1 中的模式，为 NULL 时读取 `search_path` 中的模式，不包含 pg_catalog。
+/// $1 由 `schema_param` 得到
+const SCHEMA_FILTER: &str =
+    "n.nspname::text = ANY (coalesce($1::text[], current_schemas(false)::text[]))";
+
+/// `SCHEMA_FILTER` 的参数，没有指定模式时为 NULL
+fn schema_param(schemas: &[String]) -> Option<&[String]> {
+    (!schemas.is_empty()).then_some(schemas)
+}
+
+/// 表名条件的参数从 $2 开始，$1 为 `SCHEMA_FILTER` 的参数
+fn table_filter(name: &str, filter: &Filter) -> (String, Vec<String>) {
+    filter.table_condition(name, |n| format!("${}", n + 2))
+}
+
+fn table_query<'q>(
+    sql: &'q str,
+    schemas: &'q [String],
+    patterns: &'q [String],
+) -> Query<'q, Postgres, PgArguments> {
+    patterns
+        .iter()
+        .fold(sqlx::query(sql).bind(schema_param(schemas)), |q, p| {
+            q.bind(p)
+        })
+}
+
+/// 普通函数和存储过程，不包含聚合函数、窗口函数和扩展创建的函数
+const ROUTINE_FILTER: &str = "p.prokind IN ('f', 'p') AND NOT EXISTS (
+    SELECT 1 FROM pg_catalog.pg_depend d
+    WHERE d.classid = 'pg_catalog.pg_proc'::regclass AND d.objid = p.oid AND d.deptype = 'e')";
+
+/// 按死元组比例估算的可回收空间，`st` 为 `pg_stat_all_tables`。
+/// 先转换为 numeric 再相乘，大表的乘积会超出 int8
+fn data_free(size: &str) -> String {
+    format!(
+        "sum({size}::numeric * st.n_dead_tup
+                    / nullif(st.n_live_tup + st.n_dead_tup, 0))::int8"
+    )
+}
+
+async fn tables(pool: &PgPool, schemas: &[String], filter: &Filter) -> DResult<Vec<Table>> {
+    let (tables, patterns) = table_filter("c.relname", filter);
+    let data_free = data_free("pg_table_size(l.relid)");
+    let sql = format!(
+        "SELECT n.nspname::text, c.relname::text,
+            CASE c.relkind WHEN 'p' THEN 'PARTITIONED TABLE' WHEN 'f' THEN 'FOREIGN TABLE' ELSE 'BASE TABLE' END,
+            obj_description(c.oid, 'pg_class'),
+            s.table_rows, s.data_length, s.index_length, s.data_free
+         FROM pg_catalog.pg_class c
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+         LEFT JOIN LATERAL (
+            SELECT sum(st.n_live_tup)::int8 AS table_rows,
+                sum(pg_table_size(l.relid))::int8 AS data_length,
+                sum(pg_indexes_size(l.relid))::int8 AS index_length,
+                {data_free} AS data_free
+            FROM (SELECT c.oid::regclass AS relid WHERE c.relkind <> 'p'
+                UNION ALL
+                SELECT t.relid FROM pg_partition_tree(c.oid) t WHERE c.relkind = 'p' AND t.isleaf) l
+            LEFT JOIN pg_catalog.pg_stat_all_tables st ON st.relid = l.relid
+         ) s ON true
+         WHERE c.relkind IN ('r', 'p', 'f') AND NOT c.relispartition AND {SCHEMA_FILTER}{tables}
+         ORDER BY 1, 2"
+    );
+    table_query(&sql, schemas, &patterns)
+        .try_map(|row: PgRow| {
+            let unsigned = |n: usize| -> Result<Option<u64>, sqlx::Error> {
+                Ok(row
+                    .try_get::<Option<i64>, _>(n)?
+                    .and_then(|v| u64::try_from(v).ok()))
+            };
+            Ok(Table {
+                table_schema: row.try_get(0)?,
+                table_name: row.try_get(1)?,
+                table_type: row.try_get(2)?,
+                engine: None,
+                table_comment: row.try_get(3)?,
+                character_set: None,
+                table_collation: None,
+                create_options: None,
+                fileds: Vec::new(),
+                indexs: Vec::new(),
+                constraints: Vec::new(),
+                foreign_keys: Vec::new(),
+                partitioning: None,
+                stats: TableStats {
+                    table_rows: unsigned(4)?,
+                    data_length: unsigned(5)?,
+                    index_length: unsigned(6)?,
+                    data_free: unsigned(7)?,
+                    ..Default::default()
+                },
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+/// 数组列的 data_type 为 ARRAY，column_type 为 `integer[]` 这样的完整类型。
+/// serial 和标识列的 sequence 为其使用的序列
+async fn columns(pool: &PgPool, schemas: &[String], filter: &Filter) -> DResult<Vec<TableColumn>> {
+    let (tables, patterns) = table_filter("r.relname", filter);
+    let sql = format!(
+        "SELECT n.nspname::text, r.relname::text, a.attname::text, a.attnum::int4,
+            pg_get_expr(d.adbin, d.adrelid), NOT a.attnotnull, ic.data_type::text,
+            ic.character_maximum_length::int4, ic.character_octet_length::int4,
+            ic.numeric_precision::int4, ic.numeric_scale::int4, ic.datetime_precision::int4,
+            CASE WHEN a.attcollation <> 0 THEN pg_encoding_to_char(db.encoding)::text END,
+            format_type(a.atttypid, a.atttypmod),
+            CASE WHEN a.attidentity = 'a' THEN 'ALWAYS' WHEN a.attidentity = 'd' THEN 'BY DEFAULT' END,
+            pg_get_serial_sequence(format('%I.%I', n.nspname, r.relname), a.attname),
+            col_description(r.oid, a.attnum),
+            CASE a.attgenerated WHEN 's' THEN 'STORED' END,
+            CASE WHEN a.attgenerated <> '' THEN pg_get_expr(d.adbin, d.adrelid) END,
+            CASE WHEN co.collname = 'default' THEN db.datcollate::text ELSE co.collname::text END,
+            a.attcollation <> ty.typcollation
+         FROM pg_catalog.pg_attribute a
+         JOIN pg_catalog.pg_class r ON r.oid = a.attrelid
+         JOIN pg_catalog.pg_namespace n ON n.oid = r.relnamespace
+         JOIN pg_catalog.pg_type ty ON ty.oid = a.atttypid
+         JOIN pg_catalog.pg_database db ON db.datname = current_database()
+         LEFT JOIN pg_catalog.pg_collation co ON co.oid = a.attcollation
+         LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+         LEFT JOIN information_schema.columns ic
+            ON ic.table_schema = n.nspname AND ic.table_name = r.relname AND ic.column_name = a.attname
+         WHERE r.relkind IN ('r', 'p', 'f') AND NOT r.relispartition
+            AND a.attnum > 0 AND NOT a.attisdropped AND {SCHEMA_FILTER}{tables}
+         ORDER BY 1, 2, 4"
+    );
+    table_query(&sql, schemas, &patterns)
+        .try_map(|row: PgRow| {
+            let nullable: bool = row.try_get(5)?;
+            let generated = row.try_get::<Option<String>, _>(17)?.is_some();
+            let sequence: Option<String> = row.try_get(15)?;
+            let column_type: String = row.try_get(13)?;
+            let parsed_type = coltype::parse(&column_type, Driver::Postgres);
+            let unsigned = |n: usize| -> Result<Option<u64>, sqlx::Error> {
+                Ok(row.try_get::<Option<i32>, _>(n)?.map(|v| v as u64))
+            };
+            Ok(TableColumn {
+                table_schema: row.try_get(0)?,
+                table_name: row.try_get(1)?,
+                column_name: row.try_get(2)?,
+                ordinal_position: row.try_get::<i32, _>(3)? as u32,
+                column_default: if generated { None } else { row.try_get(4)? },
+                is_nullable: if nullable { "YES" } else { "NO" }.to_string(),
+                data_type: row.try_get(6)?,
+                character_maximum_length: row.try_get::<Option<i32>, _>(7)?.map(i64::from),
+                character_octet_length: row.try_get::<Option<i32>, _>(8)?.map(i64::from),
+                numeric_precision: unsigned(9)?,
+                numeric_scale: unsigned(10)?,
+                datetime_precision: row.try_get::<Option<i32>, _>(11)?.map(|v| v as u32),
+                character_set_name: row.try_get(12)?,
+                collation_name: row.try_get(19)?,
+                explicit_collation: row.try_get(20)?,
+                rust_type: coltype::rust_type(&parsed_type, Driver::Postgres, nullable),
+                parsed_type,
+                column_type,
+                column_key: String::new(),
+                privileges: None,
+                column_comment: row.try_get::<Option<String>, _>(16)?.unwrap_or_default(),
+                identity: row.try_get(14)?,
+                auto_increment: sequence.is_some(),
+                sequence,
+                generated: row.try_get(17)?,
+                generation_expression: row.try_get(18)?,
+                extra: None,
+                on_update: None,
+                default_generated: false,
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+/// 每个键一行，不包含 INCLUDE 列。只有 btree 索引有排序方式
+async fn indexs(pool: &PgPool, schemas: &[String], filter: &Filter) -> DResult<Vec<TableIndex>> {
+    let (tables, patterns) = table_filter("t.relname", filter);
+    let sql = format!(
+        "SELECT n.nspname::text, t.relname::text, NOT x.indisunique, i.relname::text, k.n::int4,
+            a.attname::text,
+            CASE WHEN x.indkey[k.n - 1] = 0 THEN pg_get_indexdef(x.indexrelid, k.n, true) END,
+            CASE WHEN am.amname = 'btree' THEN
+                CASE WHEN x.indoption[k.n - 1] & 1::int2 = 1 THEN 'D' ELSE 'A' END END,
+            am.amname::text, pg_get_expr(x.indpred, x.indrelid),
+            obj_description(i.oid, 'pg_class'),
+            CASE WHEN st.n_distinct >= 0 THEN st.n_distinct
+                WHEN t.reltuples > 0 THEN -st.n_distinct * t.reltuples END::int8,
+            x.indisvalid
+         FROM pg_catalog.pg_index x
+         JOIN pg_catalog.pg_class i ON i.oid = x.indexrelid
+         JOIN pg_catalog.pg_class t ON t.oid = x.indrelid
+         JOIN pg_catalog.pg_namespace n ON n.oid = t.relnamespace
+         JOIN pg_catalog.pg_am am ON am.oid = i.relam
+         CROSS JOIN LATERAL generate_series(1, x.indnkeyatts) AS k(n)
+         LEFT JOIN pg_catalog.pg_attribute a
+            ON a.attrelid = x.indrelid AND a.attnum = x.indkey[k.n - 1] AND x.indkey[k.n - 1] <> 0
+         LEFT JOIN pg_catalog.pg_stats st ON st.schemaname = n.nspname AND st.tablename = t.relname
+            AND st.attname = a.attname AND st.inherited = (t.relkind = 'p')
+         WHERE t.relkind IN ('r', 'p') AND NOT t.relispartition AND {SCHEMA_FILTER}{tables}
+         ORDER BY 1, 2, 4, 5"
+    );
+    table_query(&sql, schemas, &patterns)
+        .try_map(|row: PgRow| {
+            Ok(TableIndex {
+                table_schema: row.try_get(0)?,
+                table_name: row.try_get(1)?,
+                non_unique: row.try_get(2)?,
+                index_name: row.try_get(3)?,
+                columns: vec![IndexPart {
+                    seq_in_index: row.try_get::<i32, _>(4)? as u32,
+                    column_name: row.try_get(5)?,
+                    expression: row.try_get(6)?,
+                    collation: row.try_get(7)?,
+                    sub_part: None,
+                    cardinality: row
+                        .try_get::<Option<i64>, _>(11)?
+                        .and_then(|v| u64::try_from(v).ok()),
+                }],
+                comment: String::new(),
+                index_comment: row.try_get::<Option<String>, _>(10)?.unwrap_or_default(),
+                index_type: row.try_get(8)?,
+                predicate: row.try_get(9)?,
+                visible: row.try_get(12)?,
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+async fn constraints(
+    pool: &PgPool,
+    schemas: &[String],
+    filter: &Filter,
+) -> DResult<Vec<TableConstraint>> {
+    let (tables, patterns) = table_filter("t.relname", filter);
+    let sql = format!(
+        "SELECT n.nspname::text, t.relname::text, c.conname::text,
+            CASE c.contype WHEN 'p' THEN 'PRIMARY KEY' WHEN 'u' THEN 'UNIQUE' WHEN 'c' THEN 'CHECK'
+                WHEN 'x' THEN 'EXCLUDE' ELSE c.contype::text END,
+            pg_get_constraintdef(c.oid, true)
+         FROM pg_catalog.pg_constraint c
+         JOIN pg_catalog.pg_class t ON t.oid = c.conrelid
+         JOIN pg_catalog.pg_namespace n ON n.oid = t.relnamespace
+         WHERE c.contype <> 'f' AND t.relkind IN ('r', 'p', 'f') AND NOT t.relispartition
+            AND {SCHEMA_FILTER}{tables}
+         ORDER BY 1, 2, 3"
+    );
+    table_query(&sql, schemas, &patterns)
+        .try_map(|row: PgRow| {
+            Ok(TableConstraint {
+                table_schema: row.try_get(0)?,
+                table_name: row.try_get(1)?,
+                constraint_name: row.try_get(2)?,
+                constraint_type: row.try_get(3)?,
+                definition: row.try_get(4)?,
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+/// 每行只包含外键的一对列。分区表上的外键在各分区上的副本不列出
+async fn foreign_keys(
+    pool: &PgPool,
+    schemas: &[String],
+    filter: &Filter,
+) -> DResult<Vec<ForeignKey>> {
+    let (tables, patterns) = table_filter("t.relname", filter);
+    let rule = |col: &str| {
+        format!(
+            "CASE c.{col} WHEN 'c' THEN 'CASCADE' WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT'
+                WHEN 'r' THEN 'RESTRICT' ELSE 'NO ACTION' END"
+        )
+    };
+    let sql = format!(
+        "SELECT n.nspname::text, t.relname::text, c.conname::text, a.attname::text,
+            rn.nspname::text, rt.relname::text, ra.attname::text,
+            {}, {}, c.condeferrable, c.condeferred
+         FROM pg_catalog.pg_constraint c
+         JOIN pg_catalog.pg_class t ON t.oid = c.conrelid
+         JOIN pg_catalog.pg_namespace n ON n.oid = t.relnamespace
+         JOIN pg_catalog.pg_class rt ON rt.oid = c.confrelid
+         JOIN pg_catalog.pg_namespace rn ON rn.oid = rt.relnamespace
+         CROSS JOIN LATERAL unnest(c.conkey, c.confkey) WITH ORDINALITY AS k(attnum, refnum, ord)
+         JOIN pg_catalog.pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+         JOIN pg_catalog.pg_attribute ra ON ra.attrelid = c.confrelid AND ra.attnum = k.refnum
+         WHERE c.contype = 'f' AND c.conparentid = 0 AND NOT t.relispartition AND {SCHEMA_FILTER}{tables}
+         ORDER BY 1, 2, 3, k.ord",
+        rule("confupdtype"),
+        rule("confdeltype"),
+    );
+    table_query(&sql, schemas, &patterns)
+        .try_map(|row: PgRow| {
+            Ok(ForeignKey {
+                table_schema: row.try_get(0)?,
+                table_name: row.try_get(1)?,
+                constraint_name: row.try_get(2)?,
+                columns: vec![row.try_get(3)?],
+                referenced_schema: row.try_get(4)?,
+                referenced_table: row.try_get(5)?,
+                referenced_columns: vec![row.try_get(6)?],
+                update_rule: row.try_get(7)?,
+                delete_rule: row.try_get(8)?,
+                deferrable: row.try_get(9)?,
+                initially_deferred: row.try_get(10)?,
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+/// 每行只包含一个分区和最多一个子分区，只读取两级。没有分区的分区表也有一行
+async fn partitions(
+    pool: &PgPool,
+    schemas: &[String],
+    filter: &Filter,
+) -> DResult<Vec<Partitioning>> {
+    let (tables, patterns) = table_filter("p.relname", filter);
+    let sql = format!(
+        "SELECT n.nspname::text, p.relname::text, pg_get_partkeydef(p.oid),
+            c.relname::text, (dense_rank() OVER (PARTITION BY p.oid ORDER BY c.relname))::int4,
+            pg_get_expr(c.relpartbound, c.oid), c.reltuples::int8,
+            CASE WHEN c.relkind = 'p' THEN pg_get_partkeydef(c.oid) END,
+            s.relname::text, (row_number() OVER (PARTITION BY c.oid ORDER BY s.relname))::int4,
+            pg_get_expr(s.relpartbound, s.oid), s.reltuples::int8
+         FROM pg_catalog.pg_class p
+         JOIN pg_catalog.pg_namespace n ON n.oid = p.relnamespace
+         LEFT JOIN pg_catalog.pg_inherits i ON i.inhparent = p.oid
+         LEFT JOIN pg_catalog.pg_class c ON c.oid = i.inhrelid
+         LEFT JOIN pg_catalog.pg_inherits si ON si.inhparent = c.oid
+         LEFT JOIN pg_catalog.pg_class s ON s.oid = si.inhrelid
+         WHERE p.relkind = 'p' AND NOT p.relispartition AND {SCHEMA_FILTER}{tables}
+         ORDER BY 1, 2, 5, 10"
+    );
+    table_query(&sql, schemas, &patterns)
+        .try_map(|row: PgRow| {
+            let (method, expression) = partition_key(&row.try_get::<String, _>(2)?);
+            let (subpartition_method, subpartition_expression) =
+                match row.try_get::<Option<String>, _>(7)? {
+                    Some(key) => {
+                        let (method, expression) = partition_key(&key);
+                        (Some(method), expression)
+                    }
+                    None => (None, None),
+                };
+            // 从未 ANALYZE 的表 reltuples 为 -1，分区表本身为 0
+            let rows = |n: usize| -> Result<Option<u64>, sqlx::Error> {
+                Ok(row
+                    .try_get::<Option<i64>, _>(n)?
+                    .and_then(|v| u64::try_from(v).ok()))
+            };
+            let subpartition = match row.try_get::<Option<String>, _>(8)? {
+                Some(name) => Some(Partition {
+                    partition_name: name,
+                    ordinal_position: row.try_get::<i32, _>(9)? as u32,
+                    bound: row.try_get(10)?,
+                    table_rows: rows(11)?,
+                    comment: None,
+                    subpartitions: Vec::new(),
+                }),
+                None => None,
+            };
+            let partitions = match row.try_get::<Option<String>, _>(3)? {
+                Some(name) => vec![Partition {
+                    partition_name: name,
+                    ordinal_position: row.try_get::<i32, _>(4)? as u32,
+                    bound: row.try_get(5)?,
+                    table_rows: match &subpartition {
+                        Some(sub) => sub.table_rows,
+                        None => rows(6)?,
+                    },
+                    comment: None,
+                    subpartitions: subpartition.into_iter().collect(),
+                }],
+                None => Vec::new(),
+            };
+            Ok(Partitioning {
+                table_schema: row.try_get(0)?,
+                table_name: row.try_get(1)?,
+                method,
+                expression,
+                subpartition_method,
+                subpartition_expression,
+                partitions,
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+/// 拆分 `pg_get_partkeydef` 的结果，如 `RANGE (created_at)` 为 RANGE 和 created_at
+fn partition_key(key: &str) -> (String, Option<String>) {
+    let (method, expression) = key.split_once(' ').unwrap_or((key, ""));
+    let expression = expression.trim();
+    let expression = expression
+        .strip_prefix('(')
+        .and_then(|e| e.strip_suffix(')'))
+        .unwrap_or(expression);
+    (
+        method.to_string(),
+        (!expression.is_empty()).then(|| expression.to_string()),
+    )
+}
+
+pub async fn sequences(pool: &PgPool, schemas: &[String]) -> DResult<Vec<Sequence>> {
+    let sql = "SELECT schemaname::text, sequencename::text, data_type::text, start_value,
+            min_value, max_value, increment_by, cycle, last_value
+         FROM pg_catalog.pg_sequences
+         WHERE schemaname::text = ANY (coalesce($1::text[], current_schemas(false)::text[]))
+         ORDER BY 1, 2";
+    sqlx::query(sql)
+        .bind(schema_param(schemas))
+        .try_map(|row: PgRow| {
+            Ok(Sequence {
+                sequence_schema: row.try_get(0)?,
+                sequence_name: row.try_get(1)?,
+                data_type: row.try_get(2)?,
+                start_value: row.try_get(3)?,
+                min_value: row.try_get(4)?,
+                max_value: row.try_get(5)?,
+                increment_by: row.try_get(6)?,
+                cycle: row.try_get(7)?,
+                last_value: row.try_get(8)?,
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+/// 当前数据库中的模式，不包含 pg_catalog、information_schema 等系统模式，
+/// 其他数据库需要使用另外的连接。模式没有字符集和排序规则，使用数据库的编码和 LC_COLLATE。
+/// 大小为表和物化视图（包含分区、索引和 TOAST）占用的空间
+pub async fn schemas(pool: &PgPool, filter: &Filter) -> DResult<Vec<Schema>> {
+    let sql = "SELECT n.nspname::text, pg_encoding_to_char(db.encoding)::text, db.datcollate::text,
+            count(*) FILTER (WHERE c.relkind IN ('r', 'p', 'f') AND NOT c.relispartition),
+            sum(pg_total_relation_size(c.oid)) FILTER (WHERE c.relkind IN ('r', 'm'))::int8
+         FROM pg_catalog.pg_namespace n
+         JOIN pg_catalog.pg_database db ON db.datname = current_database()
+         LEFT JOIN pg_catalog.pg_class c ON c.relnamespace = n.oid
+         WHERE n.nspname <> 'information_schema' AND n.nspname NOT LIKE 'pg\\_%'
+         GROUP BY n.nspname, db.encoding, db.datcollate
+         ORDER BY 1";
+    let mut res = sqlx::query(sql)
+        .try_map(|row: PgRow| {
+            Ok(Schema {
+                schema_name: row.try_get(0)?,
+                character_set: row.try_get(1)?,
+                collation: row.try_get(2)?,
+                table_count: row.try_get::<i64, _>(3)? as u64,
+                size: row
+                    .try_get::<Option<i64>, _>(4)?
+                    .and_then(|v| u64::try_from(v).ok()),
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))?;
+    if filter.has_rules(ObjectKind::Table) {
+        let (tables, patterns) = filter.table_condition("c.relname", |n| format!("${}", n + 1));
+        let sql = format!(
+            "SELECT n.nspname::text, c.relname::text
+             FROM pg_catalog.pg_class c
+             JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+             WHERE c.relkind IN ('r', 'p', 'f') AND NOT c.relispartition{tables}"
+        );
+        let names = patterns
+            .iter()
+            .fold(sqlx::query_as(&sql), |q, p| q.bind(p))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| Error::from(e).with_sql(&sql))?;
+        introspect::count_tables(&mut res, &names, filter);
+    }
+    Ok(res)
+}
+
+pub async fn views(pool: &PgPool, schemas: &[String]) -> DResult<Vec<View>> {
+    let sql = format!(
+        "SELECT n.nspname::text, c.relname::text, pg_get_viewdef(c.oid, true),
+            (SELECT upper(split_part(o, '=', 2)) FROM unnest(c.reloptions) o
+             WHERE o LIKE 'check_option=%'),
+            c.relkind = 'm', pg_get_userbyid(c.relowner)::text,
+            CASE WHEN EXISTS (SELECT 1 FROM unnest(c.reloptions) o
+                WHERE lower(o) IN ('security_invoker=true', 'security_invoker=on',
+                    'security_invoker=yes', 'security_invoker=1'))
+            THEN 'INVOKER' ELSE 'DEFINER' END
+         FROM pg_catalog.pg_class c
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+         WHERE c.relkind IN ('v', 'm') AND {SCHEMA_FILTER}
+         ORDER BY 1, 2"
+    );
+    sqlx::query(&sql)
+        .bind(schema_param(schemas))
+        .try_map(|row: PgRow| {
+            Ok(View {
+                view_schema: row.try_get(0)?,
+                view_name: row.try_get(1)?,
+                definition: row.try_get(2)?,
+                check_option: row.try_get(3)?,
+                materialized: row.try_get(4)?,
+                definer: row.try_get(5)?,
+                security_type: row.try_get(6)?,
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+/// 不包含约束触发器等内部触发器，分区上从父表继承的触发器不列出
+pub async fn triggers(pool: &PgPool, schemas: &[String]) -> DResult<Vec<Trigger>> {
+    let sql = format!(
+        "SELECT n.nspname::text, t.tgname::text, c.relname::text,
+            CASE WHEN t.tgtype::int & 2 <> 0 THEN 'BEFORE'
+                WHEN t.tgtype::int & 64 <> 0 THEN 'INSTEAD OF' ELSE 'AFTER' END,
+            array_remove(ARRAY[
+                CASE WHEN t.tgtype::int & 4 <> 0 THEN 'INSERT' END,
+                CASE WHEN t.tgtype::int & 16 <> 0 THEN 'UPDATE' END,
+                CASE WHEN t.tgtype::int & 8 <> 0 THEN 'DELETE' END,
+                CASE WHEN t.tgtype::int & 32 <> 0 THEN 'TRUNCATE' END], NULL),
+            CASE WHEN t.tgtype::int & 1 <> 0 THEN 'ROW' ELSE 'STATEMENT' END,
+            pg_get_triggerdef(t.oid, true), pg_get_userbyid(c.relowner)::text
+         FROM pg_catalog.pg_trigger t
+         JOIN pg_catalog.pg_class c ON c.oid = t.tgrelid
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+         WHERE NOT t.tgisinternal AND NOT c.relispartition AND {SCHEMA_FILTER}
+         ORDER BY 1, 3, 2"
+    );
+    sqlx::query(&sql)
+        .bind(schema_param(schemas))
+        .try_map(|row: PgRow| {
+            Ok(Trigger {
+                trigger_schema: row.try_get(0)?,
+                trigger_name: row.try_get(1)?,
+                table_name: row.try_get(2)?,
+                timing: row.try_get(3)?,
+                events: row.try_get(4)?,
+                orientation: row.try_get(5)?,
+                action_order: None,
+                definition: row.try_get(6)?,
+                definer: row.try_get(7)?,
+                sql_mode: None,
+            })
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+/// 函数和存储过程的参数，按 specific_name 分组，包含 OUT 参数和 RETURNS TABLE 的列
+async fn parameters(
+    pool: &PgPool,
+    schemas: &[String],
+) -> DResult<HashMap<String, Vec<RoutineParameter>>> {
+    let sql = format!(
+        "SELECT p.proname::text || '_' || p.oid::text, a.ord::int4,
+            CASE a.mode WHEN 'o' THEN 'OUT' WHEN 'b' THEN 'INOUT' WHEN 'v' THEN 'VARIADIC'
+                WHEN 't' THEN 'TABLE' ELSE 'IN' END,
+            NULLIF(a.name, ''), format_type(a.type, NULL)
+         FROM pg_catalog.pg_proc p
+         JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace
+         CROSS JOIN LATERAL unnest(coalesce(p.proallargtypes, p.proargtypes::oid[]),
+            p.proargmodes, p.proargnames) WITH ORDINALITY AS a(type, mode, name, ord)
+         WHERE {ROUTINE_FILTER} AND {SCHEMA_FILTER}
+         ORDER BY 1, 2"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(schema_param(schemas))
+        .try_map(|row: PgRow| {
+            let specific_name: String = row.try_get(0)?;
+            Ok((
+                specific_name,
+                RoutineParameter {
+                    ordinal_position: row.try_get::<i32, _>(1)? as u32,
+                    mode: row.try_get(2)?,
+                    name: row.try_get(3)?,
+                    data_type: row.try_get(4)?,
+                },
+            ))
+        })
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))?;
+
+    let mut res: HashMap<String, Vec<RoutineParameter>> = HashMap::new();
+    for (specific_name, parameter) in rows {
+        res.entry(specific_name).or_default().push(parameter);
+    }
+    Ok(res)
+}
+
+pub async fn routines(pool: &PgPool, schemas: &[String]) -> DResult<Vec<Routine>> {
+    let sql = format!(
+        "SELECT n.nspname::text, p.proname::text, p.proname::text || '_' || p.oid::text,
+            CASE p.prokind WHEN 'p' THEN 'PROCEDURE' ELSE 'FUNCTION' END,
+            CASE WHEN p.prokind = 'f' THEN pg_get_function_result(p.oid) END,
+            l.lanname::text, pg_get_functiondef(p.oid), p.provolatile = 'i',
+            pg_get_userbyid(p.proowner)::text,
+            CASE WHEN p.prosecdef THEN 'DEFINER' ELSE 'INVOKER' END,
+            obj_description(p.oid, 'pg_proc')
+         FROM pg_catalog.pg_proc p
+         JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace
+         JOIN pg_catalog.pg_language l ON l.oid = p.prolang
+         WHERE {ROUTINE_FILTER} AND {SCHEMA_FILTER}
+         ORDER BY 1, 4, 2, 3"
+    );
+    let routines = sqlx::query(&sql)
+        .bind(schema_param(schemas))
+        .try_map(|row: PgRow| {
+            Ok(Routine {
+                routine_schema: row.try_get(0)?,
+                routine_name: row.try_get(1)?,
+                specific_name: row.try_get(2)?,
+                routine_type: row.try_get(3)?,
+                parameters: Vec::new(),
+                return_type: row.try_get(4)?,
+                language: row.try_get(5)?,
+                definition: row.try_get(6)?,
+                deterministic: row.try_get(7)?,
+                data_access: None,
+                definer: row.try_get(8)?,
+                security_type: row.try_get(9)?,
+                sql_mode: None,
+                comment: row.try_get(10)?,
+            })
+        })
+        .fetch_all(pool);
+    let routines = async { routines.await.map_err(|e| Error::from(e).with_sql(&sql)) };
+    let (routines, mut parameters) = tokio::try_join!(routines, parameters(pool, schemas))?;
+    Ok(routines
+        .into_iter()
+        .map(|mut r| {
+            r.parameters = parameters.remove(&r.specific_name).unwrap_or_default();
+            r
+        })
+        .collect())
+}
+
+/// `schemas` 中各模式的表，为空时读取 `search_path` 中的模式。
+/// `filter` 中表名的 glob 规则在查询中检查，其他规则由调用方检查
+pub async fn table(pool: &PgPool, schemas: &[String], filter: &Filter) -> DResult<Vec<Table>> {
+    let (mut tables, columns, indexs, constraints, foreign_keys, partitionings) = tokio::try_join!(
+        tables(pool, schemas, filter),
+        columns(pool, schemas, filter),
+        indexs(pool, schemas, filter),
+        constraints(pool, schemas, filter),
+        foreign_keys(pool, schemas, filter),
+        partitions(pool, schemas, filter)
+    )?;
+    let parts = introspect::TableParts {
+        columns,
+        indexs,
+        constraints,
+        foreign_keys,
+        partitionings,
+    };
+    introspect::assemble(&mut tables, parts);
+    Ok(tables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 需要 PostgreSQL 服务器：
+    ///
+    /// DBTOOL_PG_URL=postgres://postgres@127.0.0.1/postgres cargo test -- --ignored
+    #[tokio::test]
+    #[ignore]
+    async fn data_free_near_int8_max() {
+        let pool = PgPool::connect(&std::env::var("DBTOOL_PG_URL").unwrap())
+            .await
+            .unwrap();
+        let sql = format!(
+            "SELECT {} FROM (VALUES ($2::int8, $3::int8)) st (n_live_tup, n_dead_tup)",
+            data_free("$1::int8")
+        );
+        let free = |size: i64, live: i64, dead: i64| {
+            sqlx::query_scalar::<_, Option<i64>>(&sql)
+                .bind(size)
+                .bind(live)
+                .bind(dead)
+                .fetch_one(&pool)
+        };
+        // 100 GB 的表有 10⁸ 个死元组，乘积约为 10¹⁹
+        let size = 100 << 30;
+        assert_eq!(
+            free(size, 100_000_000, 100_000_000).await.unwrap(),
+            Some(size / 2)
+        );
+        assert_eq!(free(i64::MAX, 0, 1_000).await.unwrap(), Some(i64::MAX));
+        assert_eq!(free(i64::MAX, 0, 0).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,158 @@
+//! 连接池管理
+//!
+//! 每个已保存的连接（按 `DbConn.id`）共享一个连接池，
+//! 连接信息修改或删除时通过 `invalidate` 关闭旧连接池。
+//! 受保护连接的连接池使用只读会话。MySQL 的只读会话不能阻止 DDL，
+//! 写操作需要先通过 `DbConn::ensure_writable` 检查。
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{MySqlPool, PgPool, SqlitePool};
+
+use crate::{
+    error::{Error, ErrorCode},
+    pg,
+    tunnel::{self, Tunnel},
+    DBConnMap, DResult, DbConn, Driver,
+};
+
+lazy_static::lazy_static! {
+    static ref POOLS: Mutex<HashMap<u64, Entry>> = Mutex::new(HashMap::new());
+}
+
+pub struct Entry {
+    pool: DbPool,
+    /// 经 SSH 隧道连接时的隧道，关闭连接池后再关闭
+    tunnel: Option<Tunnel>,
+}
+
+impl Entry {
+    async fn close(self) {
+        self.pool.close().await;
+        drop(self.tunnel);
+    }
+}
+
+/// 连接池配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolConfig {
+    /// 最大连接数
+    pub max_connections: u32,
+    /// 获取连接的超时时间（秒）
+    pub acquire_timeout: u64,
+    /// 空闲连接超过该时间（秒）后被关闭，为 0 时不关闭
+    pub idle_timeout: u64,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            acquire_timeout: 30,
+            idle_timeout: 600,
+        }
+    }
+}
+
+impl PoolConfig {
+    pub fn options<DB: sqlx::Database>(&self) -> sqlx::pool::PoolOptions<DB> {
+        let idle_timeout = match self.idle_timeout {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        sqlx::pool::PoolOptions::new()
+            .max_connections(self.max_connections.max(1))
+            .min_connections(0)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout))
+            .idle_timeout(idle_timeout)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DbPool {
+    Mysql(MySqlPool),
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+}
+
+impl DbPool {
+    /// 受保护的连接使用只读会话
+    async fn open(conn: &DbConn) -> DResult<Entry> {
+        let (conn, tunnel) = tunnel::through(conn).await?;
+        let pool = match conn.driver {
+            Driver::Mysql => Self::Mysql(
+                crate::conn_mysql(conn.mysql_options()?, &conn.pool, conn.protected).await?,
+            ),
+            Driver::Postgres => {
+                let pool = crate::conn_pg(conn.pg_options()?, &conn.pool).await?;
+                if let Err(e) = pg::check_version(&pool).await {
+                    pool.close().await;
+                    return Err(e);
+                }
+                Self::Postgres(pool)
+            }
+            Driver::Sqlite => {
+                Self::Sqlite(crate::conn_sqlite(conn.sqlite_options()?, &conn.pool).await?)
+            }
+        };
+        Ok(Entry { pool, tunnel })
+    }
+
+    pub async fn close(&self) {
+        match self {
+            Self::Mysql(p) => p.close().await,
+            Self::Postgres(p) => p.close().await,
+            Self::Sqlite(p) => p.close().await,
+        }
+    }
+
+    pub fn mysql(&self) -> DResult<&MySqlPool> {
+        match self {
+            Self::Mysql(p) => Ok(p),
+            _ => Err(Error::new(ErrorCode::Unsupported, "not a MySQL connection")),
+        }
+    }
+}
+
+/// 获取已保存连接的连接池，不存在时创建
+pub async fn get(id: u64) -> DResult<DbPool> {
+    if let Some(entry) = POOLS.lock().unwrap().get(&id) {
+        return Ok(entry.pool.clone());
+    }
+
+    let conn = saved(id)?;
+    let entry = DbPool::open(&conn).await?;
+
+    // 并发创建时保留先创建的连接池
+    let (pool, extra) = {
+        let mut lock = POOLS.lock().unwrap();
+        match lock.get(&id) {
+            Some(exist) => (exist.pool.clone(), Some(entry)),
+            None => {
+                let pool = entry.pool.clone();
+                lock.insert(id, entry);
+                (pool, None)
+            }
+        }
+    };
+    if let Some(extra) = extra {
+        extra.close().await;
+    }
+    Ok(pool)
+}
+
+fn saved(id: u64) -> DResult<DbConn> {
+    DBConnMap.lock().unwrap().get(&id).cloned().ok_or_else(|| {
+        Error::new(ErrorCode::NotFound, "connection not found").with_object(id.to_string())
+    })
+}
+
+/// 移除并关闭连接池
+pub fn invalidate(id: u64) {
+    let entry = POOLS.lock().unwrap().remove(&id);
+    if let Some(entry) = entry {
+        tauri::async_runtime::spawn(async move { entry.close().await });
+    }
+}
@@ -0,0 +1,183 @@
+//! 查询结果的一行
+//!
+//! MySQL 各版本和 MariaDB 的 information_schema 同一列的类型不同：MySQL 8 的部分列为 VARBINARY，
+//! 整数列有的有符号、有的无符号、宽度不同，有的列在一些版本中可以为 NULL 或不存在。
+//! 先按列名把每列读取为 `Value`，再转换为需要的类型；转换失败时返回包含列名和表名的
+//! `ErrorCode::Decode` 错误，而不是 panic。
+
+use sqlx::{mysql::MySqlRow, Column, MySql, Row, Type, TypeInfo, ValueRef};
+
+use crate::{
+    error::{Error, ErrorCode},
+    DResult,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Text(String),
+    /// 不是 UTF-8 的字符串或二进制
+    Bytes(Vec<u8>),
+    /// 不需要读取的类型，如未转换为字符串的时间，保存类型名
+    Other(String),
+}
+
+/// information_schema 各表中标识对象的 (模式列, 名称列)
+const OBJECT_COLUMNS: [(&str, &str); 5] = [
+    ("table_schema", "table_name"),
+    ("trigger_schema", "trigger_name"),
+    ("specific_schema", "specific_name"),
+    ("routine_schema", "routine_name"),
+    ("event_schema", "event_name"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Record {
+    /// (小写的列名, 值)
+    columns: Vec<(String, Value)>,
+}
+
+impl Record {
+    pub fn new(columns: impl IntoIterator<Item = (String, Value)>) -> Self {
+        Self {
+            columns: columns
+                .into_iter()
+                .map(|(name, value)| (name.to_lowercase(), value))
+                .collect(),
+        }
+    }
+
+    /// 读取 MySQL 的一行。字符串和二进制列都先按字节读取，是 UTF-8 时为 `Value::Text`
+    pub fn from_mysql(row: &MySqlRow) -> DResult<Self> {
+        let columns = row
+            .columns()
+            .iter()
+            .map(|column| {
+                let name = column.name().to_string();
+                mysql_value(row, column.ordinal())
+                    .map(|value| (name.clone(), value))
+                    .map_err(|e| Error::new(ErrorCode::Decode, format!("column {name}: {e}")))
+            })
+            .collect::<DResult<Vec<_>>>()?;
+        Ok(Self::new(columns))
+    }
+
+    /// 列不存在时为 None
+    pub fn get(&self, column: &str) -> Option<&Value> {
+        let column = column.to_lowercase();
+        self.columns
+            .iter()
+            .find(|(c, _)| *c == column)
+            .map(|(_, v)| v)
+    }
+
+    /// 字符串列，列不存在或为 NULL 时为 None。数字转换为字符串
+    pub fn str(&self, column: &str) -> DResult<Option<String>> {
+        match self.get(column) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::Text(s)) => Ok(Some(s.clone())),
+            Some(Value::Bytes(b)) => String::from_utf8(b.clone())
+                .map(Some)
+                .map_err(|_| self.error(column, "is not valid UTF-8")),
+            Some(Value::Int(n)) => Ok(Some(n.to_string())),
+            Some(Value::UInt(n)) => Ok(Some(n.to_string())),
+            Some(Value::Float(n)) => Ok(Some(n.to_string())),
+            Some(v @ Value::Other(_)) => Err(self.unexpected(column, "a string", v)),
+        }
+    }
+
+    /// 整数列，列不存在或为 NULL 时为 None。接受任意宽度和符号的整数，以及内容为整数的字符串
+    pub fn int<T: TryFrom<i128>>(&self, column: &str) -> DResult<Option<T>> {
+        let n: i128 = match self.get(column) {
+            None | Some(Value::Null) => return Ok(None),
+            Some(Value::Int(n)) => *n as i128,
+            Some(Value::UInt(n)) => *n as i128,
+            Some(Value::Float(n)) if n.fract() == 0.0 => *n as i128,
+            Some(v @ (Value::Text(_) | Value::Bytes(_))) => self
+                .str(column)?
+                .and_then(|s| s.trim().parse().ok())
+                .ok_or_else(|| self.unexpected(column, "an integer", v))?,
+            Some(v) => return Err(self.unexpected(column, "an integer", v)),
+        };
+        T::try_from(n)
+            .map(Some)
+            .map_err(|_| self.error(column, format!("value {n} is out of range")))
+    }
+
+    /// 不能为 NULL 的字符串列
+    pub fn text(&self, column: &str) -> DResult<String> {
+        self.str(column)?
+            .ok_or_else(|| self.error(column, "is NULL or missing"))
+    }
+
+    /// 不能为 NULL 的整数列
+    pub fn num<T: TryFrom<i128>>(&self, column: &str) -> DResult<T> {
+        self.int(column)?
+            .ok_or_else(|| self.error(column, "is NULL or missing"))
+    }
+
+    /// 这一行所属的对象，用于错误信息
+    fn object(&self) -> Option<String> {
+        let name = |c| match self.get(c) {
+            Some(Value::Text(s)) => Some(s.clone()),
+            Some(Value::Bytes(b)) => Some(String::from_utf8_lossy(b).into_owned()),
+            _ => None,
+        };
+        OBJECT_COLUMNS
+            .iter()
+            .find_map(|(schema, object)| {
+                let object = name(object)?;
+                Some(match name(schema) {
+                    Some(schema) => format!("{schema}.{object}"),
+                    None => object,
+                })
+            })
+            .or_else(|| name("schema_name"))
+    }
+
+    fn error(&self, column: &str, reason: impl std::fmt::Display) -> Error {
+        let err = Error::new(ErrorCode::Decode, format!("column {column}: {reason}"));
+        match self.object() {
+            Some(object) => err.with_object(object),
+            None => err,
+        }
+    }
+
+    fn unexpected(&self, column: &str, expected: &str, found: &Value) -> Error {
+        self.error(column, format!("expected {expected}, found {found:?}"))
+    }
+}
+
+fn mysql_value(row: &MySqlRow, index: usize) -> Result<Value, sqlx::Error> {
+    let ty = {
+        let raw = row.try_get_raw(index)?;
+        if raw.is_null() {
+            return Ok(Value::Null);
+        }
+        raw.type_info().into_owned()
+    };
+    let value = if <i64 as Type<MySql>>::compatible(&ty) {
+        Value::Int(row.try_get(index)?)
+    } else if <u64 as Type<MySql>>::compatible(&ty) {
+        Value::UInt(row.try_get(index)?)
+    } else if <f64 as Type<MySql>>::compatible(&ty) {
+        Value::Float(row.try_get(index)?)
+    } else if matches!(
+        ty.name(),
+        "DATE" | "TIME" | "DATETIME" | "TIMESTAMP" | "YEAR" | "BIT" | "GEOMETRY"
+    ) {
+        // 二进制协议中不是字符串，需要在 SQL 中 CAST 为 CHAR
+        Value::Other(ty.name().to_string())
+    } else {
+        // 字符串、二进制、DECIMAL、ENUM、SET、JSON 都按字节读取
+        let bytes: Vec<u8> = row.try_get_unchecked(index)?;
+        match String::from_utf8(bytes) {
+            Ok(s) => Value::Text(s),
+            Err(e) => Value::Bytes(e.into_bytes()),
+        }
+    };
+    Ok(value)
+}
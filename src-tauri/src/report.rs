@@ -0,0 +1,339 @@
+//! 存储空间报告
+//!
+//! 根据 `Introspector` 读取的表统计和序列按模式汇总：最大的表、索引与数据的比例、
+//! 可回收的空间，以及自增列距离类型最大值还有多远。统计都是估计值，只用于发现问题。
+
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use serde::Serialize;
+
+use crate::{coltype::ColumnType, Sequence, Table, TableColumn};
+
+/// 最大的表和可回收空间最多的表各列出的数量
+const TOP_TABLES: usize = 20;
+
+#[derive(Debug, Serialize)]
+pub struct SchemaSize {
+    pub schema: String,
+    pub table_count: usize,
+    pub table_rows: u64,
+    pub data_length: u64,
+    pub index_length: u64,
+    pub data_free: u64,
+    /// 数据和索引最大的表
+    pub largest_tables: Vec<TableSize>,
+    /// 可回收空间最多的表，不包含没有可回收空间的表
+    pub reclaimable_tables: Vec<TableSize>,
+    /// 自增列的使用情况，按使用比例从高到低
+    pub auto_increments: Vec<AutoIncrementUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableSize {
+    pub table_name: String,
+    pub table_rows: Option<u64>,
+    pub data_length: u64,
+    pub index_length: u64,
+    pub total_length: u64,
+    pub data_free: u64,
+    /// index_length / data_length，没有数据时为空
+    pub index_ratio: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AutoIncrementUsage {
+    pub table_name: String,
+    pub column_name: String,
+    pub column_type: String,
+    /// 已使用的最大值
+    pub current: u64,
+    /// 列类型或序列允许的最大值
+    pub max: u64,
+    /// current / max
+    pub usage: f64,
+}
+
+/// 按模式汇总存储空间。SQLite 的 INTEGER 为 64 位，与 PostgreSQL 的 integer 不同，由 `sqlite` 区分
+pub fn size_report(tables: &[Table], sequences: &[Sequence], sqlite: bool) -> Vec<SchemaSize> {
+    let mut schemas: BTreeMap<&str, Vec<&Table>> = BTreeMap::new();
+    for table in tables {
+        schemas.entry(&table.table_schema).or_default().push(table);
+    }
+    schemas
+        .into_iter()
+        .map(|(schema, tables)| schema_size(schema, &tables, sequences, sqlite))
+        .collect()
+}
+
+fn schema_size(
+    schema: &str,
+    tables: &[&Table],
+    sequences: &[Sequence],
+    sqlite: bool,
+) -> SchemaSize {
+    let sizes = tables.iter().map(|t| table_size(t)).collect::<Vec<_>>();
+
+    let mut largest_tables = sizes.clone();
+    largest_tables.sort_by_key(|s| Reverse(s.total_length));
+    largest_tables.truncate(TOP_TABLES);
+
+    let mut reclaimable_tables = sizes
+        .iter()
+        .filter(|s| s.data_free > 0)
+        .cloned()
+        .collect::<Vec<_>>();
+    reclaimable_tables.sort_by_key(|s| Reverse(s.data_free));
+    reclaimable_tables.truncate(TOP_TABLES);
+
+    let mut auto_increments = tables
+        .iter()
+        .flat_map(|t| {
+            t.fileds
+                .iter()
+                .filter(|c| c.auto_increment)
+                .filter_map(|c| auto_increment_usage(t, c, sequences, sqlite))
+        })
+        .collect::<Vec<_>>();
+    auto_increments.sort_by(|a, b| b.usage.total_cmp(&a.usage));
+
+    SchemaSize {
+        schema: schema.to_string(),
+        table_count: tables.len(),
+        table_rows: tables.iter().filter_map(|t| t.stats.table_rows).sum(),
+        data_length: sizes.iter().map(|s| s.data_length).sum(),
+        index_length: sizes.iter().map(|s| s.index_length).sum(),
+        data_free: sizes.iter().map(|s| s.data_free).sum(),
+        largest_tables,
+        reclaimable_tables,
+        auto_increments,
+    }
+}
+
+fn table_size(t: &Table) -> TableSize {
+    let data_length = t.stats.data_length.unwrap_or_default();
+    let index_length = t.stats.index_length.unwrap_or_default();
+    TableSize {
+        table_name: t.table_name.clone(),
+        table_rows: t.stats.table_rows,
+        data_length,
+        index_length,
+        total_length: data_length + index_length,
+        data_free: t.stats.data_free.unwrap_or_default(),
+        index_ratio: (data_length > 0).then(|| index_length as f64 / data_length as f64),
+    }
+}
+
+/// MySQL 和 SQLite 的当前值由下一个自增值得到，PostgreSQL 为列所用序列的 last_value
+fn auto_increment_usage(
+    t: &Table,
+    c: &TableColumn,
+    sequences: &[Sequence],
+    sqlite: bool,
+) -> Option<AutoIncrementUsage> {
+    let mut max = int_max(&c.parsed_type, sqlite)?;
+    let current = match &c.sequence {
+        Some(name) => {
+            // pg_get_serial_sequence 返回的名称可能带引号
+            let name = name.replace('"', "");
+            let seq = sequences
+                .iter()
+                .find(|s| format!("{}.{}", s.sequence_schema, s.sequence_name) == name)?;
+            max = max.min(u64::try_from(seq.max_value).unwrap_or(max));
+            seq.last_value
+                .map_or(0, |v| u64::try_from(v).unwrap_or_default())
+        }
+        None => t.stats.auto_increment?.saturating_sub(1),
+    };
+    Some(AutoIncrementUsage {
+        table_name: t.table_name.clone(),
+        column_name: c.column_name.clone().unwrap_or_default(),
+        column_type: c.column_type.clone(),
+        current,
+        max,
+        usage: current as f64 / max as f64,
+    })
+}
+
+/// 整数类型的最大值，不是整数类型时为空
+fn int_max(ty: &ColumnType, sqlite: bool) -> Option<u64> {
+    let (signed_max, unsigned_max) = match ty.base.as_str() {
+        "tinyint" => (i8::MAX as u64, u8::MAX as u64),
+        "smallint" | "int2" => (i16::MAX as u64, u16::MAX as u64),
+        "mediumint" => (8_388_607, 16_777_215),
+        "int" | "integer" | "int4" if !sqlite => (i32::MAX as u64, u32::MAX as u64),
+        "int" | "integer" | "bigint" | "int8" => (i64::MAX as u64, u64::MAX),
+        _ => return None,
+    };
+    Some(if ty.unsigned {
+        unsigned_max
+    } else {
+        signed_max
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coltype, Driver, TableStats};
+
+    fn column(column_type: &str, driver: Driver, sequence: Option<&str>) -> TableColumn {
+        TableColumn {
+            table_schema: String::new(),
+            table_name: String::new(),
+            column_name: Some("id".to_string()),
+            ordinal_position: 1,
+            column_default: None,
+            is_nullable: "NO".to_string(),
+            data_type: None,
+            character_maximum_length: None,
+            character_octet_length: None,
+            numeric_precision: None,
+            numeric_scale: None,
+            datetime_precision: None,
+            character_set_name: None,
+            collation_name: None,
+            explicit_collation: false,
+            column_type: column_type.to_string(),
+            parsed_type: coltype::parse(column_type, driver),
+            rust_type: String::new(),
+            column_key: "PRI".to_string(),
+            privileges: None,
+            column_comment: String::new(),
+            identity: None,
+            sequence: sequence.map(str::to_string),
+            generated: None,
+            generation_expression: None,
+            extra: None,
+            auto_increment: true,
+            on_update: None,
+            default_generated: false,
+        }
+    }
+
+    fn table(name: &str, stats: TableStats, fileds: Vec<TableColumn>) -> Table {
+        Table {
+            table_schema: "shop".to_string(),
+            table_name: name.to_string(),
+            table_type: "BASE TABLE".to_string(),
+            engine: None,
+            table_comment: None,
+            character_set: None,
+            table_collation: None,
+            create_options: None,
+            fileds,
+            indexs: Vec::new(),
+            constraints: Vec::new(),
+            foreign_keys: Vec::new(),
+            partitioning: None,
+            stats,
+        }
+    }
+
+    fn sequence(name: &str, max_value: i64, last_value: Option<i64>) -> Sequence {
+        Sequence {
+            sequence_schema: "public".to_string(),
+            sequence_name: name.to_string(),
+            data_type: "integer".to_string(),
+            start_value: 1,
+            min_value: 1,
+            max_value,
+            increment_by: 1,
+            cycle: false,
+            last_value,
+        }
+    }
+
+    #[test]
+    fn int_max_by_driver() {
+        let max = |ty: &str, driver: Driver| {
+            int_max(&coltype::parse(ty, driver), driver == Driver::Sqlite)
+        };
+        assert_eq!(max("integer", Driver::Postgres), Some(i32::MAX as u64));
+        assert_eq!(max("INTEGER", Driver::Sqlite), Some(i64::MAX as u64));
+        assert_eq!(max("int(11)", Driver::Mysql), Some(i32::MAX as u64));
+        assert_eq!(
+            max("int(10) unsigned", Driver::Mysql),
+            Some(u32::MAX as u64)
+        );
+        assert_eq!(max("tinyint", Driver::Mysql), Some(127));
+        assert_eq!(max("mediumint unsigned", Driver::Mysql), Some(16_777_215));
+        assert_eq!(max("bigint unsigned", Driver::Mysql), Some(u64::MAX));
+        assert_eq!(max("smallint", Driver::Postgres), Some(i16::MAX as u64));
+        assert_eq!(max("varchar(20)", Driver::Mysql), None);
+    }
+
+    #[test]
+    fn auto_increment_usage_order() {
+        let stats = |auto_increment| TableStats {
+            auto_increment: Some(auto_increment),
+            ..Default::default()
+        };
+        let tables = [
+            table(
+                "small",
+                stats(101),
+                vec![column("tinyint unsigned", Driver::Mysql, None)],
+            ),
+            table("big", stats(11), vec![column("int", Driver::Mysql, None)]),
+            // PostgreSQL 的序列名带引号
+            table(
+                "Orders",
+                TableStats::default(),
+                vec![column(
+                    "integer",
+                    Driver::Postgres,
+                    Some("public.\"Orders_id_seq\""),
+                )],
+            ),
+        ];
+        let sequences = [sequence("Orders_id_seq", i64::MAX, Some(1 << 30))];
+
+        let report = size_report(&tables, &sequences, false);
+        let usages = report[0]
+            .auto_increments
+            .iter()
+            .map(|u| (u.table_name.as_str(), u.current, u.max))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            usages,
+            [
+                ("Orders", 1 << 30, i32::MAX as u64),
+                ("small", 100, 255),
+                ("big", 10, i32::MAX as u64),
+            ]
+        );
+
+        // 序列的最大值小于类型的最大值，尚未使用的序列为 0
+        let sequences = [sequence("Orders_id_seq", 1000, None)];
+        let report = size_report(&tables[2..], &sequences, false);
+        let usage = &report[0].auto_increments[0];
+        assert_eq!((usage.current, usage.max), (0, 1000));
+    }
+
+    #[test]
+    fn top_tables() {
+        let tables = (0..TOP_TABLES as u64 + 5)
+            .map(|n| {
+                let stats = TableStats {
+                    data_length: Some(n * 100),
+                    index_length: Some(50),
+                    data_free: Some(n % 2 * n),
+                    ..Default::default()
+                };
+                table(&format!("t{n}"), stats, Vec::new())
+            })
+            .collect::<Vec<_>>();
+
+        let report = size_report(&tables, &[], false);
+        let schema = &report[0];
+        assert_eq!(schema.table_count, TOP_TABLES + 5);
+        assert_eq!(schema.largest_tables.len(), TOP_TABLES);
+        assert_eq!(schema.largest_tables[0].table_name, "t24");
+        assert_eq!(schema.largest_tables[0].index_ratio, Some(50.0 / 2400.0));
+        // 只有奇数表有可回收空间
+        assert_eq!(schema.reclaimable_tables.len(), 12);
+        assert_eq!(schema.reclaimable_tables[0].table_name, "t23");
+        // 没有数据的表没有索引比例
+        assert_eq!(table_size(&tables[0]).index_ratio, None);
+    }
+}
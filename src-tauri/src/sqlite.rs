@@ -0,0 +1,1099 @@
+//! SQLite 表结构
+//!
+//! 基于 `sqlite_schema` 和 `pragma_table_list`、`pragma_table_xinfo`、`pragma_index_list`、
+//! `pragma_index_xinfo`、`pragma_foreign_key_list` 读取 main 库的表。
+//! 生成列表达式、部分索引条件和表达式索引的键 PRAGMA 不返回，从建表、建索引语句中解析，
+//! 视图的查询和触发器的时机、事件同样从 `sqlite_schema.sql` 中解析。
+
+use std::collections::HashMap;
+
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+use crate::{
+    coltype,
+    error::Error,
+    filter::{Filter, ObjectKind},
+    DResult, Driver, ForeignKey, IndexPart, Schema, Table, TableColumn, TableConstraint,
+    TableIndex, TableStats, Trigger, View,
+};
+
+const SCHEMA: &str = "main";
+
+/// 不包含 sqlite_ 开头的内部表
+const TABLE_FILTER: &str = "m.type = 'table' AND m.name NOT LIKE 'sqlite\\_%' ESCAPE '\\'";
+
+struct TableRow {
+    name: String,
+    table_type: String,
+    without_rowid: bool,
+    strict: bool,
+    sql: String,
+}
+
+struct ColumnRow {
+    table_name: String,
+    cid: i64,
+    name: String,
+    ty: String,
+    not_null: bool,
+    default: Option<String>,
+    pk: i64,
+    /// 0 普通列，2 VIRTUAL 生成列，3 STORED 生成列。虚拟表的隐藏列（1）不读取
+    hidden: i64,
+}
+
+struct IndexRow {
+    table_name: String,
+    name: String,
+    unique: bool,
+    partial: bool,
+    sql: Option<String>,
+}
+
+struct IndexKeyRow {
+    index_name: String,
+    seqno: i64,
+    /// -1 为 rowid，-2 为表达式
+    cid: i64,
+    name: Option<String>,
+    desc: bool,
+}
+
+struct ForeignKeyRow {
+    table_name: String,
+    id: i64,
+    parent: String,
+    from: String,
+    to: Option<String>,
+    on_update: String,
+    on_delete: String,
+}
+
+async fn fetch<T, F>(pool: &SqlitePool, sql: &str, f: F) -> DResult<Vec<T>>
+where
+    F: Fn(&SqliteRow) -> Result<T, sqlx::Error> + Send + 'static,
+    T: Send + Unpin,
+{
+    sqlx::query(sql)
+        .try_map(move |row: SqliteRow| f(&row))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| Error::from(e).with_sql(sql))
+}
+
+/// 与 `fetch` 相同，表不存在时返回空，用于 dbstat 和 sqlite_sequence。
+/// 编译 SQLite 时未启用 dbstat，或者还没有 AUTOINCREMENT 列时，这两个表不存在
+async fn fetch_optional<T, F>(pool: &SqlitePool, sql: &str, f: F) -> DResult<Vec<T>>
+where
+    F: Fn(&SqliteRow) -> Result<T, sqlx::Error> + Send + 'static,
+    T: Send + Unpin,
+{
+    match sqlx::query(sql)
+        .try_map(move |row: SqliteRow| f(&row))
+        .fetch_all(pool)
+        .await
+    {
+        Err(sqlx::Error::Database(e)) if e.message().starts_with("no such table") => Ok(Vec::new()),
+        res => res.map_err(|e| Error::from(e).with_sql(sql)),
+    }
+}
+
+async fn tables(pool: &SqlitePool, where_tables: &str) -> DResult<Vec<TableRow>> {
+    let sql = format!(
+        "SELECT t.name, t.type, t.wr, t.strict, m.sql
+         FROM sqlite_schema m JOIN pragma_table_list t ON t.name = m.name
+         WHERE t.schema = 'main' AND {TABLE_FILTER}{where_tables}
+         ORDER BY m.name"
+    );
+    fetch(pool, &sql, |row| {
+        Ok(TableRow {
+            name: row.try_get(0)?,
+            table_type: row.try_get(1)?,
+            without_rowid: row.try_get(2)?,
+            strict: row.try_get(3)?,
+            sql: row.try_get::<Option<String>, _>(4)?.unwrap_or_default(),
+        })
+    })
+    .await
+}
+
+async fn columns(pool: &SqlitePool, where_tables: &str) -> DResult<Vec<ColumnRow>> {
+    let sql = format!(
+        "SELECT m.name, p.cid, p.name, p.type, p.\"notnull\", p.dflt_value, p.pk, p.hidden
+         FROM sqlite_schema m JOIN pragma_table_xinfo(m.name) p
+         WHERE {TABLE_FILTER} AND p.hidden <> 1{where_tables}
+         ORDER BY m.name, p.cid"
+    );
+    fetch(pool, &sql, |row| {
+        Ok(ColumnRow {
+            table_name: row.try_get(0)?,
+            cid: row.try_get(1)?,
+            name: row.try_get(2)?,
+            ty: row.try_get(3)?,
+            not_null: row.try_get(4)?,
+            default: row.try_get(5)?,
+            pk: row.try_get(6)?,
+            hidden: row.try_get(7)?,
+        })
+    })
+    .await
+}
+
+async fn indexs(pool: &SqlitePool, where_tables: &str) -> DResult<Vec<IndexRow>> {
+    let sql = format!(
+        "SELECT m.name, il.name, il.\"unique\", il.partial, s.sql
+         FROM sqlite_schema m JOIN pragma_index_list(m.name) il
+         LEFT JOIN sqlite_schema s ON s.type = 'index' AND s.name = il.name
+         WHERE {TABLE_FILTER}{where_tables}
+         ORDER BY m.name, il.name"
+    );
+    fetch(pool, &sql, |row| {
+        Ok(IndexRow {
+            table_name: row.try_get(0)?,
+            name: row.try_get(1)?,
+            unique: row.try_get(2)?,
+            partial: row.try_get(3)?,
+            sql: row.try_get(4)?,
+        })
+    })
+    .await
+}
+
+async fn index_keys(pool: &SqlitePool, where_tables: &str) -> DResult<Vec<IndexKeyRow>> {
+    let sql = format!(
+        "SELECT il.name, x.seqno, x.cid, x.name, x.\"desc\"
+         FROM sqlite_schema m JOIN pragma_index_list(m.name) il JOIN pragma_index_xinfo(il.name) x
+         WHERE {TABLE_FILTER}{where_tables} AND x.key
+         ORDER BY il.name, x.seqno"
+    );
+    fetch(pool, &sql, |row| {
+        Ok(IndexKeyRow {
+            index_name: row.try_get(0)?,
+            seqno: row.try_get(1)?,
+            cid: row.try_get(2)?,
+            name: row.try_get(3)?,
+            desc: row.try_get(4)?,
+        })
+    })
+    .await
+}
+
+async fn foreign_keys(pool: &SqlitePool, where_tables: &str) -> DResult<Vec<ForeignKeyRow>> {
+    let sql = format!(
+        "SELECT m.name, f.id, f.\"table\", f.\"from\", f.\"to\", f.on_update, f.on_delete
+         FROM sqlite_schema m JOIN pragma_foreign_key_list(m.name) f
+         WHERE {TABLE_FILTER}{where_tables}
+         ORDER BY m.name, f.id, f.seq"
+    );
+    fetch(pool, &sql, |row| {
+        Ok(ForeignKeyRow {
+            table_name: row.try_get(0)?,
+            id: row.try_get(1)?,
+            parent: row.try_get(2)?,
+            from: row.try_get(3)?,
+            to: row.try_get(4)?,
+            on_update: row.try_get(5)?,
+            on_delete: row.try_get(6)?,
+        })
+    })
+    .await
+}
+
+/// 各表的行数和页占用，索引的页计入所属的表
+async fn stats(pool: &SqlitePool) -> DResult<HashMap<String, TableStats>> {
+    let sql = "SELECT m.tbl_name,
+            coalesce(sum(CASE WHEN m.type = 'table' AND d.pagetype = 'leaf' THEN d.ncell END), 0),
+            coalesce(sum(CASE WHEN m.type = 'table' THEN d.pgsize END), 0),
+            coalesce(sum(CASE WHEN m.type = 'index' THEN d.pgsize END), 0),
+            coalesce(sum(d.unused), 0)
+         FROM dbstat d JOIN sqlite_schema m ON m.name = d.name
+         WHERE m.type IN ('table', 'index')
+            AND m.tbl_name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+         GROUP BY m.tbl_name";
+    let sequence = "SELECT name, seq + 1 FROM sqlite_sequence";
+    let unsigned = |row: &SqliteRow, n: usize| -> Result<Option<u64>, sqlx::Error> {
+        Ok(u64::try_from(row.try_get::<i64, _>(n)?).ok())
+    };
+    let (pages, sequences) = tokio::try_join!(
+        fetch_optional(pool, sql, move |row| {
+            Ok((
+                row.try_get::<String, _>(0)?,
+                TableStats {
+                    table_rows: unsigned(row, 1)?,
+                    data_length: unsigned(row, 2)?,
+                    index_length: unsigned(row, 3)?,
+                    data_free: unsigned(row, 4)?,
+                    ..Default::default()
+                },
+            ))
+        }),
+        fetch_optional(pool, sequence, move |row| {
+            Ok((row.try_get::<String, _>(0)?, unsigned(row, 1)?))
+        })
+    )?;
+
+    let mut res = pages.into_iter().collect::<HashMap<_, _>>();
+    for (name, next) in sequences {
+        res.entry(name).or_default().auto_increment = next;
+    }
+    Ok(res)
+}
+
+/// sqlite_stat1 中各索引的统计，执行过 ANALYZE 才有
+async fn index_stats(pool: &SqlitePool) -> DResult<HashMap<String, Vec<u64>>> {
+    let sql = "SELECT idx, stat FROM sqlite_stat1 WHERE idx IS NOT NULL";
+    let rows = fetch_optional(pool, sql, |row| {
+        let stat: String = row.try_get(1)?;
+        // 数字之后可能还有 unordered、sz=N 等选项
+        let stat = stat
+            .split_whitespace()
+            .map_while(|n| n.parse::<u64>().ok())
+            .collect::<Vec<_>>();
+        Ok((row.try_get::<String, _>(0)?, stat))
+    })
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// `filter` 中表名的 glob 规则在查询中检查，其他规则由调用方检查
+pub async fn table(pool: &SqlitePool, schemas: &[String], filter: &Filter) -> DResult<Vec<Table>> {
+    if !reads_main(schemas) {
+        return Ok(Vec::new());
+    }
+    let where_tables = table_filter(filter);
+    let (tables, columns, indexs, index_keys, foreign_keys, mut stats, index_stats) = tokio::try_join!(
+        tables(pool, &where_tables),
+        columns(pool, &where_tables),
+        indexs(pool, &where_tables),
+        index_keys(pool, &where_tables),
+        foreign_keys(pool, &where_tables),
+        stats(pool),
+        index_stats(pool)
+    )?;
+
+    let mut keys: HashMap<String, Vec<IndexKeyRow>> = HashMap::new();
+    for key in index_keys {
+        keys.entry(key.index_name.clone()).or_default().push(key);
+    }
+
+    let mut res = Vec::with_capacity(tables.len());
+    for t in tables {
+        let mut table = new_table(&t);
+        for c in columns.iter().filter(|c| c.table_name == t.name) {
+            table.fileds.push(column(&t, c));
+        }
+        let cols = primary_key(&columns, &t.name);
+        if !cols.is_empty() {
+            table.constraints.push(constraint(
+                &t.name,
+                "PRIMARY",
+                "PRIMARY KEY",
+                format!("PRIMARY KEY ({})", cols.join(", ")),
+            ));
+        }
+        for (name, definition) in checks(&t.name, &t.sql) {
+            table
+                .constraints
+                .push(constraint(&t.name, &name, "CHECK", definition));
+        }
+        for i in indexs.iter().filter(|i| i.table_name == t.name) {
+            let parts = keys.get(&i.name).map(Vec::as_slice).unwrap_or_default();
+            let stat = index_stats
+                .get(&i.name)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            table.indexs.push(index(&t.name, i, parts, stat));
+        }
+        let fks = foreign_keys.iter().filter(|f| f.table_name == t.name);
+        table.foreign_keys = table_foreign_keys(&t, fks, &columns);
+        table.stats = stats.remove(&t.name).unwrap_or_default();
+        res.push(table);
+    }
+    Ok(res)
+}
+
+/// 只读取主数据库，字符集为数据库的文本编码，大小为数据库文件的大小
+/// 表数量与 `table` 一样按 `filter` 统计
+pub async fn schemas(pool: &SqlitePool, filter: &Filter) -> DResult<Vec<Schema>> {
+    let sql = format!(
+        "SELECT m.name FROM sqlite_schema m WHERE {TABLE_FILTER}{}",
+        table_filter(filter)
+    );
+    let names = fetch(pool, &sql, |row| row.try_get::<String, _>(0)).await?;
+    let table_count = names
+        .iter()
+        .filter(|name| filter.matches(ObjectKind::Table, name))
+        .count() as u64;
+    let sql = "SELECT (SELECT encoding FROM pragma_encoding),
+            (SELECT page_count * page_size FROM pragma_page_count, pragma_page_size)";
+    fetch(pool, sql, move |row| {
+        Ok(Schema {
+            schema_name: SCHEMA.to_string(),
+            character_set: row.try_get(0)?,
+            collation: None,
+            table_count,
+            size: row
+                .try_get::<Option<i64>, _>(1)?
+                .and_then(|v| u64::try_from(v).ok()),
+        })
+    })
+    .await
+}
+
+/// 表名的 glob 规则转换为 GLOB 条件，以 AND 开头。GLOB 区分大小写，
+/// `*`、`?` 与规则的含义相同，`[` 需要写成 `[[]`
+fn table_filter(filter: &Filter) -> String {
+    let glob = |g: &str| {
+        format!(
+            "m.name GLOB '{}'",
+            g.replace('[', "[[]").replace('\'', "''")
+        )
+    };
+    let (include, exclude) = filter.table_globs();
+    let mut sql = String::new();
+    if !include.is_empty() {
+        let globs = include.into_iter().map(glob).collect::<Vec<_>>();
+        sql.push_str(&format!(" AND ({})", globs.join(" OR ")));
+    }
+    for g in exclude {
+        sql.push_str(&format!(" AND NOT {}", glob(g)));
+    }
+    sql
+}
+
+/// ATTACH 的数据库只对一个连接有效，连接池中只能读取 main 库。
+/// `schemas` 为空或包含 main 时读取
+fn reads_main(schemas: &[String]) -> bool {
+    schemas.is_empty() || schemas.iter().any(|s| s == SCHEMA)
+}
+
+pub async fn views(pool: &SqlitePool, schemas: &[String]) -> DResult<Vec<View>> {
+    if !reads_main(schemas) {
+        return Ok(Vec::new());
+    }
+    let sql = "SELECT name, sql FROM sqlite_schema WHERE type = 'view' ORDER BY name";
+    fetch(pool, sql, |row| {
+        let sql: String = row.try_get(1)?;
+        Ok(View {
+            view_schema: SCHEMA.to_string(),
+            view_name: row.try_get(0)?,
+            definition: view_query(&sql).unwrap_or(&sql).to_string(),
+            check_option: None,
+            materialized: false,
+            definer: None,
+            security_type: None,
+        })
+    })
+    .await
+}
+
+pub async fn triggers(pool: &SqlitePool, schemas: &[String]) -> DResult<Vec<Trigger>> {
+    if !reads_main(schemas) {
+        return Ok(Vec::new());
+    }
+    let sql = "SELECT name, tbl_name, sql FROM sqlite_schema WHERE type = 'trigger'
+         ORDER BY tbl_name, name";
+    fetch(pool, sql, |row| {
+        let sql: String = row.try_get(2)?;
+        let (timing, event) = trigger_event(&sql);
+        Ok(Trigger {
+            trigger_schema: SCHEMA.to_string(),
+            trigger_name: row.try_get(0)?,
+            table_name: row.try_get(1)?,
+            timing: timing.to_string(),
+            events: event.map(str::to_string).into_iter().collect(),
+            // SQLite 只支持 FOR EACH ROW
+            orientation: "ROW".to_string(),
+            action_order: None,
+            definition: sql,
+            definer: None,
+            sql_mode: None,
+        })
+    })
+    .await
+}
+
+fn new_table(t: &TableRow) -> Table {
+    let mut options = Vec::new();
+    if t.without_rowid {
+        options.push("WITHOUT ROWID");
+    }
+    if t.strict {
+        options.push("STRICT");
+    }
+    Table {
+        table_schema: SCHEMA.to_string(),
+        table_name: t.name.clone(),
+        table_type: match t.table_type.as_str() {
+            "virtual" => "VIRTUAL TABLE",
+            _ => "BASE TABLE",
+        }
+        .to_string(),
+        engine: None,
+        table_comment: None,
+        character_set: None,
+        table_collation: None,
+        create_options: (!options.is_empty()).then(|| options.join(", ")),
+        fileds: Vec::new(),
+        indexs: Vec::new(),
+        constraints: Vec::new(),
+        foreign_keys: Vec::new(),
+        partitioning: None,
+        stats: TableStats::default(),
+    }
+}
+
+fn column(t: &TableRow, c: &ColumnRow) -> TableColumn {
+    let generated = match c.hidden {
+        2 => Some("VIRTUAL".to_string()),
+        3 => Some("STORED".to_string()),
+        _ => None,
+    };
+    let generation_expression = generated
+        .as_ref()
+        .and_then(|_| generation_expression(&t.sql, &c.name));
+    let def = column_def(&t.sql, &c.name);
+    let parsed_type = coltype::parse(&c.ty, Driver::Sqlite);
+    // 列定义中的 COLLATE 子句，没有时使用默认的 BINARY
+    let collation_name = def
+        .and_then(|def| find_keyword(def, "COLLATE").map(|i| &def[i + "COLLATE".len()..]))
+        .map(|rest| unquote(first_token(rest.trim_start())).to_string());
+    // 声明类型中括号前的部分，如 VARCHAR(20) 为 VARCHAR
+    let data_type =
+        c.ty.split('(')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_uppercase();
+    TableColumn {
+        table_schema: SCHEMA.to_string(),
+        table_name: t.name.clone(),
+        column_name: Some(c.name.clone()),
+        ordinal_position: c.cid as u32 + 1,
+        column_default: c.default.clone(),
+        is_nullable: if c.not_null { "NO" } else { "YES" }.to_string(),
+        data_type: (!data_type.is_empty()).then_some(data_type),
+        character_maximum_length: None,
+        character_octet_length: None,
+        numeric_precision: None,
+        numeric_scale: None,
+        datetime_precision: None,
+        character_set_name: None,
+        explicit_collation: collation_name.is_some(),
+        collation_name,
+        rust_type: coltype::rust_type(&parsed_type, Driver::Sqlite, !c.not_null),
+        parsed_type,
+        column_type: c.ty.clone(),
+        column_key: if c.pk > 0 { "PRI" } else { "" }.to_string(),
+        privileges: None,
+        column_comment: String::new(),
+        identity: None,
+        sequence: None,
+        generated,
+        generation_expression,
+        extra: None,
+        auto_increment: def.is_some_and(|def| find_keyword(def, "AUTOINCREMENT").is_some()),
+        on_update: None,
+        default_generated: false,
+    }
+}
+
+/// `stat` 为 sqlite_stat1 中的估计值，第一个为总行数，之后为每个键前缀平均重复的行数
+fn index(table_name: &str, i: &IndexRow, keys: &[IndexKeyRow], stat: &[u64]) -> TableIndex {
+    let sql = i.sql.as_deref().unwrap_or_default();
+    let exprs = index_columns(sql);
+    let columns = keys
+        .iter()
+        .map(|k| {
+            let (column_name, expression) = match (&k.name, k.cid) {
+                (Some(name), _) => (Some(name.clone()), None),
+                (None, -1) => (Some("rowid".to_string()), None),
+                (None, _) => (None, exprs.get(k.seqno as usize).cloned()),
+            };
+            IndexPart {
+                seq_in_index: k.seqno as u32 + 1,
+                column_name,
+                expression,
+                collation: Some(if k.desc { "D" } else { "A" }.to_string()),
+                sub_part: None,
+                cardinality: match (stat.first(), stat.get(k.seqno as usize + 1)) {
+                    (Some(rows), Some(&n)) if n > 0 => Some(rows.div_ceil(n)),
+                    _ => None,
+                },
+            }
+        })
+        .collect();
+    TableIndex {
+        table_schema: SCHEMA.to_string(),
+        table_name: table_name.to_string(),
+        non_unique: !i.unique,
+        index_name: i.name.clone(),
+        columns,
+        comment: String::new(),
+        index_comment: String::new(),
+        index_type: None,
+        predicate: if i.partial {
+            index_predicate(sql)
+        } else {
+            None
+        },
+        visible: true,
+    }
+}
+
+fn constraint(table_name: &str, name: &str, ty: &str, definition: String) -> TableConstraint {
+    TableConstraint {
+        table_schema: SCHEMA.to_string(),
+        table_name: table_name.to_string(),
+        constraint_name: name.to_string(),
+        constraint_type: ty.to_string(),
+        definition,
+    }
+}
+
+/// 按 id 合并外键的各列。PRAGMA 不返回外键名和延迟检查，从建表语句中解析，
+/// 未命名的外键使用 `表名_fk_id` 作为约束名
+fn table_foreign_keys<'a>(
+    t: &TableRow,
+    rows: impl Iterator<Item = &'a ForeignKeyRow>,
+    columns: &[ColumnRow],
+) -> Vec<ForeignKey> {
+    let mut groups: Vec<(i64, Vec<&ForeignKeyRow>)> = Vec::new();
+    for row in rows {
+        match groups.last_mut() {
+            Some((id, group)) if *id == row.id => group.push(row),
+            _ => groups.push((row.id, vec![row])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(id, group)| {
+            let first = group[0];
+            let from = group.iter().map(|r| r.from.clone()).collect::<Vec<_>>();
+            // to 为空时引用父表主键
+            let to = if group.iter().all(|r| r.to.is_some()) {
+                group.iter().filter_map(|r| r.to.clone()).collect()
+            } else {
+                primary_key(columns, &first.parent)
+            };
+            let clause = foreign_key_clause(&t.sql, &from);
+            let name = clause
+                .and_then(constraint_name)
+                .unwrap_or_else(|| format!("{}_fk_{id}", t.name));
+            let deferrable = clause.is_some_and(|c| {
+                find_keyword(c, "DEFERRABLE").is_some()
+                    && find_keyword(c, "NOT DEFERRABLE").is_none()
+            });
+            ForeignKey {
+                table_schema: SCHEMA.to_string(),
+                table_name: t.name.clone(),
+                constraint_name: name,
+                columns: from,
+                referenced_schema: SCHEMA.to_string(),
+                referenced_table: first.parent.clone(),
+                referenced_columns: to,
+                update_rule: first.on_update.clone(),
+                delete_rule: first.on_delete.clone(),
+                deferrable,
+                initially_deferred: deferrable
+                    && clause.is_some_and(|c| find_keyword(c, "INITIALLY DEFERRED").is_some()),
+            }
+        })
+        .collect()
+}
+
+fn primary_key(columns: &[ColumnRow], table_name: &str) -> Vec<String> {
+    let mut pk = columns
+        .iter()
+        .filter(|c| c.table_name == table_name && c.pk > 0)
+        .map(|c| (c.pk, c.name.clone()))
+        .collect::<Vec<_>>();
+    pk.sort();
+    pk.into_iter().map(|(_, name)| name).collect()
+}
+
+/// 从 `pos` 处的左括号开始，返回匹配的右括号之前的内容，忽略引号中的括号
+fn paren_body(sql: &str, pos: usize) -> Option<&str> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, ch) in sql[pos..].char_indices() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(ch),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&sql[pos + 1..pos + i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 按顶层逗号拆分，忽略括号和引号中的逗号
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, ch) in s.char_indices() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(ch),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts
+}
+
+/// 去掉标识符两侧的引号
+fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    for (l, r) in [('"', '"'), ('`', '`'), ('[', ']'), ('\'', '\'')] {
+        if s.len() >= 2 && s.starts_with(l) && s.ends_with(r) {
+            return &s[1..s.len() - 1];
+        }
+    }
+    s
+}
+
+/// 在 `sql` 中查找不在引号中的关键字（不区分大小写）。
+/// 只匹配前后不是标识符字符的位置
+fn find_keyword(sql: &str, keyword: &str) -> Option<usize> {
+    let upper = sql.to_ascii_uppercase();
+    let is_ident = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+    let mut quote = None;
+    for (i, ch) in sql.char_indices() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(ch),
+            (None, '[') => quote = Some(']'),
+            _ if upper[i..].starts_with(keyword)
+                && !is_ident(sql[..i].chars().next_back())
+                && !is_ident(sql[i + keyword.len()..].chars().next()) =>
+            {
+                return Some(i);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 定义开头的标识符，可以带引号
+fn first_token(def: &str) -> &str {
+    match def.chars().next() {
+        Some('"' | '`' | '[' | '\'') => {
+            let end = def[1..]
+                .find(['"', '`', ']', '\''])
+                .map_or(def.len(), |i| i + 2);
+            &def[..end]
+        }
+        _ => def.split_whitespace().next().unwrap_or_default(),
+    }
+}
+
+/// 建表语句括号中的各个列定义和表约束
+fn definitions(table_sql: &str) -> Vec<&str> {
+    table_sql
+        .find('(')
+        .and_then(|open| paren_body(table_sql, open))
+        .map(split_top_level)
+        .unwrap_or_default()
+}
+
+fn column_def<'a>(table_sql: &'a str, column: &str) -> Option<&'a str> {
+    definitions(table_sql)
+        .into_iter()
+        .find(|def| unquote(first_token(def)).eq_ignore_ascii_case(column))
+}
+
+/// 从建表语句中解析生成列的表达式
+fn generation_expression(table_sql: &str, column: &str) -> Option<String> {
+    let def = column_def(table_sql, column)?;
+    let pos = find_keyword(def, "AS")?;
+    let open = pos + def[pos..].find('(')?;
+    paren_body(def, open).map(|s| s.trim().to_string())
+}
+
+/// 建表语句中的 CHECK 约束，包括列约束。未命名的约束使用 `表名_chk_序号` 作为约束名
+fn checks(table_name: &str, table_sql: &str) -> Vec<(String, String)> {
+    let mut res = Vec::new();
+    for def in definitions(table_sql) {
+        let Some(pos) = find_keyword(def, "CHECK") else {
+            continue;
+        };
+        let Some(body) = def[pos..]
+            .find('(')
+            .and_then(|open| paren_body(def, pos + open))
+        else {
+            continue;
+        };
+        let name = find_keyword(def, "CONSTRAINT")
+            .filter(|&c| c < pos)
+            .and_then(|c| constraint_name(&def[c..]))
+            .unwrap_or_else(|| format!("{table_name}_chk_{}", res.len() + 1));
+        res.push((name, format!("CHECK ({})", body.trim())));
+    }
+    res
+}
+
+/// 外键列为 `from` 的外键定义，可以是表约束 `FOREIGN KEY (a, b) REFERENCES ...`，
+/// 也可以是单列的列约束 `a INTEGER REFERENCES ...`
+fn foreign_key_clause<'a>(table_sql: &'a str, from: &[String]) -> Option<&'a str> {
+    let same = |cols: Vec<&str>| {
+        cols.len() == from.len()
+            && cols
+                .iter()
+                .zip(from)
+                .all(|(a, b)| unquote(a).eq_ignore_ascii_case(b))
+    };
+    definitions(table_sql)
+        .into_iter()
+        .find(|def| match find_keyword(def, "FOREIGN KEY") {
+            Some(pos) if def[..pos].trim().is_empty() || constraint_name(def).is_some() => def
+                [pos..]
+                .find('(')
+                .and_then(|open| paren_body(def, pos + open))
+                .is_some_and(|cols| same(split_top_level(cols))),
+            _ => same(vec![first_token(def)]) && find_keyword(def, "REFERENCES").is_some(),
+        })
+}
+
+/// `CONSTRAINT name ...` 中的约束名
+fn constraint_name(def: &str) -> Option<String> {
+    let rest = def.trim_start();
+    if !rest.get(..10)?.eq_ignore_ascii_case("CONSTRAINT") {
+        return None;
+    }
+    let name = first_token(rest[10..].trim_start());
+    (!name.is_empty()).then(|| unquote(name).to_string())
+}
+
+/// 建索引语句 `ON t (a, lower(b))` 中的各键
+fn index_columns(index_sql: &str) -> Vec<String> {
+    let Some(on) = find_keyword(index_sql, "ON") else {
+        return Vec::new();
+    };
+    let Some(open) = index_sql[on..].find('(').map(|i| on + i) else {
+        return Vec::new();
+    };
+    paren_body(index_sql, open)
+        .map(|body| {
+            split_top_level(body)
+                .into_iter()
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 部分索引 WHERE 之后的条件
+fn index_predicate(index_sql: &str) -> Option<String> {
+    let pos = find_keyword(index_sql, "WHERE")?;
+    Some(index_sql[pos + "WHERE".len()..].trim().to_string())
+}
+
+/// 建视图语句 AS 之后的查询
+fn view_query(view_sql: &str) -> Option<&str> {
+    let pos = find_keyword(view_sql, "AS")?;
+    Some(view_sql[pos + "AS".len()..].trim())
+}
+
+/// 建触发器语句 BEGIN 之前的时机和事件，省略时机时为 BEFORE
+fn trigger_event(trigger_sql: &str) -> (&'static str, Option<&'static str>) {
+    let head = &trigger_sql[..find_keyword(trigger_sql, "BEGIN").unwrap_or(trigger_sql.len())];
+    let timing = ["INSTEAD OF", "AFTER", "BEFORE"]
+        .into_iter()
+        .find(|t| find_keyword(head, t).is_some())
+        .unwrap_or("BEFORE");
+    let event = ["DELETE", "INSERT", "UPDATE"]
+        .into_iter()
+        .filter_map(|e| find_keyword(head, e).map(|pos| (pos, e)))
+        .min()
+        .map(|(_, e)| e);
+    (timing, event)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::filter::{FilterRule, ObjectFilter, Syntax};
+
+    const SCHEMA_SQL: &str = r#"
+        CREATE TABLE author (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            CHECK (length(name) > 0)
+        ) STRICT;
+        CREATE TABLE book (
+            isbn TEXT NOT NULL,
+            author_id INTEGER REFERENCES author (id) ON DELETE CASCADE,
+            title TEXT COLLATE NOCASE,
+            price REAL DEFAULT 0 CONSTRAINT book_price CHECK (price >= 0),
+            "title lower" TEXT GENERATED ALWAYS AS (lower(title)) VIRTUAL,
+            price_cents INTEGER AS (CAST(price * 100 AS INTEGER)) STORED,
+            editor_id INTEGER,
+            PRIMARY KEY (isbn),
+            CONSTRAINT book_editor FOREIGN KEY (editor_id) REFERENCES author
+                DEFERRABLE INITIALLY DEFERRED
+        ) WITHOUT ROWID;
+        CREATE INDEX book_cheap ON book (price) WHERE price < 10;
+        CREATE UNIQUE INDEX book_title ON book (lower(title), author_id DESC);
+        CREATE VIEW cheap_book (isbn, title) AS SELECT isbn, title FROM book WHERE price < 10;
+        CREATE TRIGGER book_audit AFTER UPDATE OF price ON book
+        BEGIN
+            SELECT 1;
+        END;
+    "#;
+
+    async fn open() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::raw_sql(SCHEMA_SQL).execute(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn read_schema() {
+        let tables = table(&open().await, &[], &Filter::default()).await.unwrap();
+        assert_eq!(
+            tables
+                .iter()
+                .map(|t| t.table_name.as_str())
+                .collect::<Vec<_>>(),
+            ["author", "book"]
+        );
+
+        let author = &tables[0];
+        assert_eq!(author.create_options.as_deref(), Some("STRICT"));
+        assert_eq!(author.fileds[0].column_key, "PRI");
+        assert!(author.fileds[0].auto_increment);
+        assert_eq!(author.stats.table_rows, Some(0));
+        assert!(author.stats.data_length.is_some_and(|n| n > 0));
+        assert!(!author.fileds[1].auto_increment);
+        let checks = author
+            .constraints
+            .iter()
+            .filter(|c| c.constraint_type == "CHECK")
+            .map(|c| (c.constraint_name.as_str(), c.definition.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(checks, [("author_chk_1", "CHECK (length(name) > 0)")]);
+
+        let book = &tables[1];
+        assert_eq!(book.create_options.as_deref(), Some("WITHOUT ROWID"));
+        let names = book
+            .fileds
+            .iter()
+            .filter_map(|c| c.column_name.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "isbn",
+                "author_id",
+                "title",
+                "price",
+                "title lower",
+                "price_cents",
+                "editor_id"
+            ]
+        );
+        assert_eq!(book.fileds[0].is_nullable, "NO");
+        assert_eq!(book.fileds[3].column_default.as_deref(), Some("0"));
+        assert_eq!(book.fileds[2].collation_name.as_deref(), Some("NOCASE"));
+        assert!(book.fileds[2].explicit_collation);
+        assert_eq!(book.fileds[3].collation_name, None);
+
+        let lower = &book.fileds[4];
+        assert_eq!(lower.generated.as_deref(), Some("VIRTUAL"));
+        assert_eq!(lower.generation_expression.as_deref(), Some("lower(title)"));
+        let cents = &book.fileds[5];
+        assert_eq!(cents.generated.as_deref(), Some("STORED"));
+        assert_eq!(
+            cents.generation_expression.as_deref(),
+            Some("CAST(price * 100 AS INTEGER)")
+        );
+
+        let index = |name: &str| book.indexs.iter().find(|i| i.index_name == name).unwrap();
+        let cheap = index("book_cheap");
+        assert!(cheap.non_unique);
+        assert_eq!(cheap.predicate.as_deref(), Some("price < 10"));
+        let title = index("book_title");
+        assert!(!title.non_unique);
+        assert_eq!(title.columns.len(), 2);
+        assert_eq!(title.columns[0].column_name, None);
+        assert_eq!(title.columns[0].expression.as_deref(), Some("lower(title)"));
+        assert_eq!(title.columns[1].column_name.as_deref(), Some("author_id"));
+        assert_eq!(title.columns[1].collation.as_deref(), Some("D"));
+        assert!(title.visible);
+
+        let definitions = book
+            .constraints
+            .iter()
+            .map(|c| c.definition.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(definitions, ["PRIMARY KEY (isbn)", "CHECK (price >= 0)"]);
+        assert_eq!(book.constraints[1].constraint_name, "book_price");
+
+        let fk = |column: &str| {
+            book.foreign_keys
+                .iter()
+                .find(|f| f.columns == [column])
+                .unwrap()
+        };
+        let author_fk = fk("author_id");
+        assert!(author_fk.constraint_name.starts_with("book_fk_"));
+        assert_eq!(author_fk.referenced_table, "author");
+        assert_eq!(author_fk.referenced_columns, ["id"]);
+        assert_eq!(author_fk.delete_rule, "CASCADE");
+        assert_eq!(author_fk.update_rule, "NO ACTION");
+        assert!(!author_fk.deferrable);
+        let editor_fk = fk("editor_id");
+        assert_eq!(editor_fk.constraint_name, "book_editor");
+        assert_eq!(editor_fk.referenced_columns, ["id"]);
+        assert!(editor_fk.deferrable);
+        assert!(editor_fk.initially_deferred);
+    }
+
+    #[tokio::test]
+    async fn index_cardinality() {
+        let pool = open().await;
+        sqlx::raw_sql(
+            "INSERT INTO book (isbn, price) VALUES ('a', 1), ('b', 1), ('c', 2), ('d', 2);
+             ANALYZE;",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let tables = table(&pool, &[], &Filter::default()).await.unwrap();
+        let cheap = tables[1]
+            .indexs
+            .iter()
+            .find(|i| i.index_name == "book_cheap")
+            .unwrap();
+        assert_eq!(cheap.columns[0].cardinality, Some(2));
+    }
+
+    #[tokio::test]
+    async fn read_views_and_triggers() {
+        let pool = open().await;
+        let views = views(&pool, &[]).await.unwrap();
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].view_name, "cheap_book");
+        assert_eq!(
+            views[0].definition,
+            "SELECT isbn, title FROM book WHERE price < 10"
+        );
+
+        let triggers = triggers(&pool, &[]).await.unwrap();
+        assert_eq!(triggers.len(), 1);
+        let audit = &triggers[0];
+        assert_eq!(audit.table_name, "book");
+        assert_eq!(audit.timing, "AFTER");
+        assert_eq!(audit.events, ["UPDATE"]);
+        assert!(audit.definition.starts_with("CREATE TRIGGER book_audit"));
+    }
+
+    #[tokio::test]
+    async fn read_schemas() {
+        let pool = open().await;
+        let schemas = schemas(&pool, &Filter::default()).await.unwrap();
+        assert_eq!(schemas.len(), 1);
+        assert_eq!(schemas[0].schema_name, "main");
+        assert_eq!(schemas[0].character_set.as_deref(), Some("UTF-8"));
+        assert_eq!(schemas[0].table_count, 2);
+        assert!(schemas[0].size.is_some_and(|n| n > 0));
+        // 表数量与 table 读取的表一致
+        let filter = ObjectFilter {
+            rules: vec![FilterRule {
+                kinds: vec![ObjectKind::Table],
+                syntax: Syntax::Regex,
+                pattern: "^auth".to_string(),
+                exclude: false,
+            }],
+        }
+        .compile()
+        .unwrap();
+        let filtered = super::schemas(&pool, &filter).await.unwrap();
+        assert_eq!(filtered[0].table_count, 1);
+
+        let other = ["other".to_string()];
+        assert!(table(&pool, &other, &Filter::default())
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(views(&pool, &other).await.unwrap().is_empty());
+        let main = ["main".to_string()];
+        assert_eq!(
+            table(&pool, &main, &Filter::default()).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn skip_hidden_columns() {
+        let pool = open().await;
+        sqlx::raw_sql("CREATE VIRTUAL TABLE note USING fts5 (title, body)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let filter = ObjectFilter {
+            rules: vec![FilterRule {
+                kinds: Vec::new(),
+                syntax: Syntax::Glob,
+                pattern: "note".to_string(),
+                exclude: false,
+            }],
+        }
+        .compile()
+        .unwrap();
+        let tables = table(&pool, &[], &filter).await.unwrap();
+        let names = tables[0]
+            .fileds
+            .iter()
+            .filter_map(|c| c.column_name.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["title", "body"]);
+    }
+
+    #[tokio::test]
+    async fn filter_tables() {
+        let pool = open().await;
+        let rule = |pattern: &str, exclude| FilterRule {
+            kinds: Vec::new(),
+            syntax: Syntax::Glob,
+            pattern: pattern.to_string(),
+            exclude,
+        };
+        let filter = ObjectFilter {
+            rules: vec![rule("*o*", false), rule("b??k", true)],
+        }
+        .compile()
+        .unwrap();
+        let tables = table(&pool, &[], &filter).await.unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].table_name, "author");
+        assert_eq!(tables[0].fileds.len(), 2);
+    }
+}
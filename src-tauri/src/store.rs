@@ -0,0 +1,191 @@
+//! 数据库连接信息持久化
+//!
+//! 连接信息保存在应用配置目录下的 `dbconn.json` 中，文件格式带版本号，
+//! `DbConn` 增加字段后通过 `MIGRATIONS` 将旧文件升级到当前版本。
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::{Error, ErrorCode},
+    vault, DResult, DbConn,
+};
+
+const STORE_FILE: &str = "dbconn.json";
+
+/// 当前文件格式版本
+const STORE_VERSION: u64 = 3;
+
+/// 文件格式升级步骤，`MIGRATIONS[n]` 将版本 `n + 1` 升级到版本 `n + 2`
+const MIGRATIONS: &[fn(&mut Value) -> DResult<()>] = &[encrypt_passwords, move_ssl_mode];
+
+lazy_static::lazy_static! {
+    static ref STORE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    /// 加载成功后才设置，未加载时不能保存，避免覆盖未读取的连接
+    static ref STORE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u64,
+    conns: Vec<DbConn>,
+}
+
+/// 设置存储目录并加载已保存的连接。
+/// 旧版本文件升级时需要加密密码，密钥锁定时返回 `ErrorCode::VaultLocked`，解锁后再调用 `load`
+pub fn init(dir: PathBuf) -> DResult<Vec<DbConn>> {
+    fs::create_dir_all(&dir).map_err(|e| io_error(e, &dir))?;
+    vault::init(&dir)?;
+    *STORE_DIR.lock().unwrap() = Some(dir);
+    load()
+}
+
+/// 是否已加载，未加载时不能保存
+pub fn loaded() -> bool {
+    STORE_PATH.lock().unwrap().is_some()
+}
+
+/// 加载已保存的连接
+pub fn load() -> DResult<Vec<DbConn>> {
+    let Some(dir) = STORE_DIR.lock().unwrap().clone() else {
+        return Err(not_initialized());
+    };
+    let path = dir.join(STORE_FILE);
+    let conns = if path.exists() {
+        read(&path)?
+    } else {
+        Vec::new()
+    };
+    *STORE_PATH.lock().unwrap() = Some(path);
+    Ok(conns)
+}
+
+fn not_initialized() -> Error {
+    Error::new(ErrorCode::Store, "connection store is not initialized")
+}
+
+fn read(path: &Path) -> DResult<Vec<DbConn>> {
+    let content = fs::read_to_string(path).map_err(|e| io_error(e, path))?;
+    let mut value: Value = serde_json::from_str(&content).map_err(|e| corrupted(e, path))?;
+    migrate(&mut value).map_err(|e| e.with_object(path.display().to_string()))?;
+    let file: StoreFile = serde_json::from_value(value).map_err(|e| corrupted(e, path))?;
+    Ok(file.conns)
+}
+
+fn io_error(e: std::io::Error, path: &Path) -> Error {
+    Error::new(ErrorCode::Io, e.to_string()).with_object(path.display().to_string())
+}
+
+fn corrupted(e: serde_json::Error, path: &Path) -> Error {
+    Error::new(
+        ErrorCode::Store,
+        format!("connection store is corrupted: {e}"),
+    )
+    .with_object(path.display().to_string())
+}
+
+/// 将旧版本文件升级到 `STORE_VERSION`
+fn migrate(value: &mut Value) -> DResult<()> {
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| Error::new(ErrorCode::Store, "connection store has no version"))?;
+    if version == 0 || version > STORE_VERSION {
+        return Err(Error::new(
+            ErrorCode::Store,
+            format!("unsupported connection store version {version}"),
+        ));
+    }
+    for step in &MIGRATIONS[(version - 1) as usize..] {
+        step(value)?;
+    }
+    value["version"] = Value::from(STORE_VERSION);
+    Ok(())
+}
+
+/// v1 -> v2: 明文 `password` 加密为 `secret`
+fn encrypt_passwords(value: &mut Value) -> DResult<()> {
+    let Some(conns) = value.get_mut("conns").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    for conn in conns.iter_mut().filter_map(Value::as_object_mut) {
+        if let Some(Value::String(password)) = conn.remove("password") {
+            if !password.is_empty() {
+                conn.insert("secret".into(), vault::encrypt(&password)?.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// v2 -> v3: `options.mysql.ssl_mode`、`options.postgres.ssl_mode` 移到 `tls.mode`
+fn move_ssl_mode(value: &mut Value) -> DResult<()> {
+    let Some(conns) = value.get_mut("conns").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    for conn in conns.iter_mut().filter_map(Value::as_object_mut) {
+        let driver = conn
+            .get("driver")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let mut mode = None;
+        if let Some(options) = conn.get_mut("options").and_then(Value::as_object_mut) {
+            for (name, opts) in options.iter_mut() {
+                let Some(ssl_mode) = opts.as_object_mut().and_then(|o| o.remove("ssl_mode")) else {
+                    continue;
+                };
+                if *name == driver {
+                    mode = Some(ssl_mode);
+                }
+            }
+        }
+        if let Some(mode) = mode {
+            conn.insert("tls".into(), serde_json::json!({ "mode": mode }));
+        }
+    }
+    Ok(())
+}
+
+/// 保存全部连接，先写临时文件再重命名，避免写入中断导致文件损坏
+pub fn save(conns: &HashMap<u64, DbConn>) -> DResult<()> {
+    let Some(path) = STORE_PATH.lock().unwrap().clone() else {
+        if STORE_DIR.lock().unwrap().is_some() {
+            return Err(Error::new(
+                ErrorCode::VaultLocked,
+                "saved connections are not loaded, unlock the vault first",
+            ));
+        }
+        return Err(not_initialized());
+    };
+
+    let mut conns = conns.values().cloned().collect::<Vec<_>>();
+    conns.sort_by_key(|c| c.id);
+    let file = StoreFile {
+        version: STORE_VERSION,
+        conns,
+    };
+    let content = serde_json::to_vec_pretty(&file).map_err(|e| {
+        Error::new(
+            ErrorCode::Store,
+            format!("encode connection store failed: {e}"),
+        )
+    })?;
+
+    let tmp = path.with_extension("json.tmp");
+    let write = || -> std::io::Result<()> {
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(&content)?;
+        f.sync_all()?;
+        fs::rename(&tmp, &path)
+    };
+    write().map_err(|e| io_error(e, &path))
+}
@@ -0,0 +1,523 @@
+//! SSH 隧道
+//!
+//! 通过跳板机连接数据库时，先在本地 127.0.0.1 的随机端口监听，
+//! 每个本地连接经 SSH direct-tcpip 通道转发到数据库地址。
+//! 隧道随连接池一起创建和关闭。
+
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use ssh2::{Channel, CheckResult, KnownHostFileKind, Session};
+
+use crate::{
+    error::{Error, ErrorCode},
+    options::SslMode,
+    vault, DResult, DbConn, Driver,
+};
+
+/// SSH 连接超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// 没有数据可转发时的等待时间
+const IDLE_SLEEP: Duration = Duration::from_millis(5);
+/// 心跳间隔（秒）
+const KEEPALIVE_INTERVAL: u32 = 30;
+/// libssh2 非阻塞模式下的 LIBSSH2_ERROR_EAGAIN
+const LIBSSH2_ERROR_EAGAIN: i32 = -37;
+
+/// SSH 隧道配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshConfig {
+    pub enabled: bool,
+    /// 跳板机地址
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// 明文密码，只在编辑时由界面传入，为 None 表示保持不变
+    #[serde(skip_serializing)]
+    pub password: Option<String>,
+    /// 加密后的密码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password_secret: Option<String>,
+    /// 私钥文件路径，设置后使用私钥认证
+    pub private_key: Option<String>,
+    /// 私钥密码明文，只在编辑时由界面传入
+    #[serde(skip_serializing)]
+    pub passphrase: Option<String>,
+    /// 加密后的私钥密码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase_secret: Option<String>,
+    /// known_hosts 文件，默认 ~/.ssh/known_hosts
+    pub known_hosts: Option<String>,
+    /// 主机密钥不在 known_hosts 中时仍然连接，密钥不匹配时始终拒绝
+    pub accept_unknown_host: bool,
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 22,
+            username: String::new(),
+            password: None,
+            password_secret: None,
+            private_key: None,
+            passphrase: None,
+            passphrase_secret: None,
+            known_hosts: None,
+            accept_unknown_host: false,
+        }
+    }
+}
+
+impl SshConfig {
+    fn password(&self) -> DResult<Option<String>> {
+        reveal(&self.password, &self.password_secret)
+    }
+
+    fn passphrase(&self) -> DResult<Option<String>> {
+        reveal(&self.passphrase, &self.passphrase_secret)
+    }
+
+    fn known_hosts_path(&self) -> Option<PathBuf> {
+        match self.known_hosts.as_deref().filter(|p| !p.is_empty()) {
+            Some(path) => Some(expand_home(path)),
+            None => dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts")),
+        }
+    }
+
+    /// 清除加密后的密码，用于返回给界面
+    pub fn redact(&mut self) {
+        self.password = None;
+        self.password_secret = None;
+        self.passphrase = None;
+        self.passphrase_secret = None;
+    }
+}
+
+/// 展开路径开头的 `~/`
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// 优先使用界面传入的明文，否则解密已保存的密文
+fn reveal(plain: &Option<String>, secret: &Option<String>) -> DResult<Option<String>> {
+    if let Some(plain) = plain {
+        return Ok(Some(plain.clone()));
+    }
+    secret.as_deref().map(vault::decrypt).transpose()
+}
+
+/// 配置了 SSH 隧道时打开隧道，返回改为连接本地转发端口的连接信息，
+/// 隧道需要与使用它的连接池保持相同的生命周期
+pub async fn through(conn: &DbConn) -> DResult<(DbConn, Option<Tunnel>)> {
+    if !conn.ssh.enabled || conn.driver == Driver::Sqlite {
+        return Ok((conn.clone(), None));
+    }
+    check_tls(conn)?;
+    let cfg = conn.ssh.clone();
+    let host = conn.host.clone();
+    let port = conn.port;
+    let tunnel = tauri::async_runtime::spawn_blocking(move || Tunnel::open(&cfg, &host, port))
+        .await
+        .map_err(|e| ssh_error(format!("start ssh tunnel failed: {e}")))??;
+    let local = DbConn {
+        host: "127.0.0.1".to_string(),
+        port: tunnel.local_port(),
+        ..conn.clone()
+    };
+    Ok((local, Some(tunnel)))
+}
+
+/// sqlx 用连接的主机名校验证书，经隧道时主机名为 127.0.0.1，
+/// 无法按原主机名校验，verify-full 不能与 SSH 隧道一起使用
+fn check_tls(conn: &DbConn) -> DResult<()> {
+    if conn.tls.mode == SslMode::VerifyFull {
+        return Err(Error::new(
+            ErrorCode::Config,
+            "TLS verify-full cannot verify the server hostname through an SSH tunnel, use verify-ca",
+        )
+        .with_object(&conn.name));
+    }
+    Ok(())
+}
+
+/// 本地端口转发，drop 时停止转发
+pub struct Tunnel {
+    local_port: u16,
+    shutdown: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Tunnel {
+    /// 连接跳板机并开始转发到 `target_host:target_port`，会阻塞直到认证完成
+    pub fn open(cfg: &SshConfig, target_host: &str, target_port: u16) -> DResult<Self> {
+        let session = connect(cfg)?;
+
+        let bind_error =
+            |e: std::io::Error| ssh_error(format!("bind local tunnel port failed: {e}"));
+        let listener = TcpListener::bind(("127.0.0.1", 0)).map_err(bind_error)?;
+        let local_port = listener.local_addr().map_err(bind_error)?.port();
+        listener.set_nonblocking(true).map_err(bind_error)?;
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let forward = Forward {
+            session,
+            listener,
+            target_host: target_host.to_string(),
+            target_port,
+            shutdown: shutdown.clone(),
+        };
+        let worker = thread::Builder::new()
+            .name(format!("ssh-tunnel-{local_port}"))
+            .spawn(move || forward.run())
+            .map_err(|e| ssh_error(format!("start ssh tunnel failed: {e}")))?;
+
+        Ok(Self {
+            local_port,
+            shutdown,
+            worker: Some(worker),
+        })
+    }
+
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn ssh_error(message: impl Into<String>) -> Error {
+    Error::new(ErrorCode::Ssh, message)
+}
+
+/// 连接跳板机，校验主机密钥并认证
+fn connect(cfg: &SshConfig) -> DResult<Session> {
+    let addr = (cfg.host.as_str(), cfg.port)
+        .to_socket_addrs()
+        .map_err(|e| ssh_error(format!("resolve ssh host failed: {e}")).with_object(&cfg.host))?
+        .next()
+        .ok_or_else(|| ssh_error("resolve ssh host failed").with_object(&cfg.host))?;
+    let tcp = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(|e| {
+        ssh_error(format!("connect ssh host failed: {e}")).with_object(addr.to_string())
+    })?;
+
+    let mut session =
+        Session::new().map_err(|e| ssh_error(format!("create ssh session failed: {e}")))?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(CONNECT_TIMEOUT.as_millis() as u32);
+    session.handshake().map_err(|e| {
+        ssh_error(format!("ssh handshake failed: {e}")).with_object(addr.to_string())
+    })?;
+
+    verify_host_key(&session, cfg)?;
+
+    match cfg.private_key.as_deref().filter(|p| !p.is_empty()) {
+        Some(key) => {
+            let passphrase = cfg.passphrase()?;
+            session
+                .userauth_pubkey_file(
+                    &cfg.username,
+                    None,
+                    &expand_home(key),
+                    passphrase.as_deref(),
+                )
+                .map_err(|e| {
+                    ssh_error(format!("ssh key authentication failed: {e}"))
+                        .with_object(&cfg.username)
+                })?;
+        }
+        None => {
+            let password = cfg.password()?.unwrap_or_default();
+            session
+                .userauth_password(&cfg.username, &password)
+                .map_err(|e| {
+                    ssh_error(format!("ssh password authentication failed: {e}"))
+                        .with_object(&cfg.username)
+                })?;
+        }
+    }
+    if !session.authenticated() {
+        return Err(ssh_error("ssh authentication failed").with_object(&cfg.username));
+    }
+
+    session.set_keepalive(true, KEEPALIVE_INTERVAL);
+    session.set_timeout(0);
+    Ok(session)
+}
+
+fn verify_host_key(session: &Session, cfg: &SshConfig) -> DResult<()> {
+    let (key, _) = session
+        .host_key()
+        .ok_or_else(|| ssh_error("ssh host key missing"))?;
+    let mut known = session
+        .known_hosts()
+        .map_err(|e| ssh_error(format!("read known_hosts failed: {e}")))?;
+    if let Some(path) = cfg.known_hosts_path().filter(|p| p.is_file()) {
+        known
+            .read_file(&path, KnownHostFileKind::OpenSSH)
+            .map_err(|e| {
+                ssh_error(format!("read known_hosts failed: {e}"))
+                    .with_object(path.display().to_string())
+            })?;
+    }
+    let host = || format!("{}:{}", cfg.host, cfg.port);
+    match known.check_port(&cfg.host, cfg.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::NotFound if cfg.accept_unknown_host => {
+            log::warn!("ssh host {}:{} is not in known_hosts", cfg.host, cfg.port);
+            Ok(())
+        }
+        CheckResult::NotFound => {
+            Err(ssh_error("ssh host key is not in known_hosts").with_object(host()))
+        }
+        CheckResult::Mismatch => {
+            Err(ssh_error("ssh host key does not match known_hosts").with_object(host()))
+        }
+        CheckResult::Failure => Err(ssh_error("check ssh host key failed").with_object(host())),
+    }
+}
+
+struct Forward {
+    session: Session,
+    listener: TcpListener,
+    target_host: String,
+    target_port: u16,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Forward {
+    fn run(self) {
+        self.session.set_blocking(false);
+        let mut pipes: Vec<Pipe> = Vec::new();
+        let mut keepalive_at = Instant::now();
+
+        while !self.shutdown.load(Ordering::Relaxed) {
+            let mut progress = false;
+
+            match self.listener.accept() {
+                Ok((client, _)) => {
+                    progress = true;
+                    match self.open_channel() {
+                        Ok(channel) if client.set_nonblocking(true).is_ok() => {
+                            pipes.push(Pipe::new(client, channel))
+                        }
+                        Ok(_) => {}
+                        Err(e) => log::error!(
+                            "open ssh channel to {}:{} failed: {e}",
+                            self.target_host,
+                            self.target_port
+                        ),
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    log::error!("ssh tunnel accept failed: {e}");
+                    break;
+                }
+            }
+
+            pipes.retain_mut(|pipe| match pipe.pump() {
+                Ok(moved) => {
+                    progress |= moved;
+                    !pipe.done()
+                }
+                Err(e) => {
+                    log::debug!("ssh tunnel connection closed: {e}");
+                    false
+                }
+            });
+
+            if keepalive_at.elapsed() >= Duration::from_secs(KEEPALIVE_INTERVAL as u64) {
+                let _ = self.session.keepalive_send();
+                keepalive_at = Instant::now();
+            }
+            if !progress {
+                thread::sleep(IDLE_SLEEP);
+            }
+        }
+    }
+
+    fn open_channel(&self) -> Result<Channel, ssh2::Error> {
+        let start = Instant::now();
+        loop {
+            match self
+                .session
+                .channel_direct_tcpip(&self.target_host, self.target_port, None)
+            {
+                Err(e)
+                    if e.code() == ssh2::ErrorCode::Session(LIBSSH2_ERROR_EAGAIN)
+                        && start.elapsed() < CONNECT_TIMEOUT =>
+                {
+                    thread::sleep(IDLE_SLEEP)
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+/// 一个本地连接与 SSH 通道之间的双向转发
+struct Pipe {
+    client: TcpStream,
+    channel: Channel,
+    /// 待写入通道的数据
+    to_remote: Vec<u8>,
+    /// 待写入本地连接的数据
+    to_local: Vec<u8>,
+    client_eof: bool,
+}
+
+impl Pipe {
+    fn new(client: TcpStream, channel: Channel) -> Self {
+        Self {
+            client,
+            channel,
+            to_remote: Vec::new(),
+            to_local: Vec::new(),
+            client_eof: false,
+        }
+    }
+
+    /// 数据库端已关闭且数据已全部转发
+    fn done(&self) -> bool {
+        self.channel.eof() && self.to_local.is_empty()
+    }
+
+    /// 转发一轮数据，返回是否有数据被转发
+    fn pump(&mut self) -> std::io::Result<bool> {
+        let mut progress = false;
+        let mut buf = [0u8; 16 * 1024];
+
+        if self.to_remote.is_empty() && !self.client_eof {
+            match self.client.read(&mut buf) {
+                Ok(0) => {
+                    self.client_eof = true;
+                    let _ = self.channel.send_eof();
+                    progress = true;
+                }
+                Ok(n) => {
+                    self.to_remote.extend_from_slice(&buf[..n]);
+                    progress = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if !self.to_remote.is_empty() {
+            match self.channel.write(&self.to_remote) {
+                Ok(n) => {
+                    self.to_remote.drain(..n);
+                    progress = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if self.to_local.is_empty() {
+            match self.channel.read(&mut buf) {
+                Ok(0) => {}
+                Ok(n) => {
+                    self.to_local.extend_from_slice(&buf[..n]);
+                    progress = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if !self.to_local.is_empty() {
+            match self.client.write(&self.to_local) {
+                Ok(n) => {
+                    self.to_local.drain(..n);
+                    progress = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+
+    use super::*;
+
+    #[test]
+    fn verify_full_through_tunnel() {
+        let mut conn: DbConn = serde_json::from_value(serde_json::json!({
+            "driver": "postgres",
+            "name": "pg",
+            "host": "db.internal",
+            "port": 5432,
+            "username": "postgres",
+            "password": "",
+            "database": "postgres",
+            "tls": { "mode": "verify-full" },
+            "ssh": { "enabled": true, "host": "bastion", "username": "me" },
+        }))
+        .unwrap();
+        let err = check_tls(&conn).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Config);
+        assert_eq!(err.object.as_deref(), Some("pg"));
+
+        conn.tls.mode = SslMode::VerifyCa;
+        assert!(check_tls(&conn).is_ok());
+    }
+
+    /// 需要本地 sshd，通过隧道连接 sshd 自身并读取其版本标识：
+    ///
+    /// DBTOOL_SSH_USER=me DBTOOL_SSH_KEY=~/.ssh/id_ed25519 cargo test -- --ignored
+    #[test]
+    #[ignore]
+    fn forward_through_local_sshd() {
+        let cfg = SshConfig {
+            enabled: true,
+            host: "127.0.0.1".to_string(),
+            port: std::env::var("DBTOOL_SSH_PORT")
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(22),
+            username: std::env::var("DBTOOL_SSH_USER").unwrap(),
+            password: std::env::var("DBTOOL_SSH_PASSWORD").ok(),
+            private_key: std::env::var("DBTOOL_SSH_KEY").ok(),
+            passphrase: std::env::var("DBTOOL_SSH_PASSPHRASE").ok(),
+            accept_unknown_host: true,
+            ..Default::default()
+        };
+        let tunnel = Tunnel::open(&cfg, "127.0.0.1", cfg.port).unwrap();
+
+        let stream = TcpStream::connect(("127.0.0.1", tunnel.local_port())).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut banner = String::new();
+        BufReader::new(stream).read_line(&mut banner).unwrap();
+        assert!(banner.starts_with("SSH-2.0"), "{banner}");
+    }
+}
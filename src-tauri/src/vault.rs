@@ -0,0 +1,295 @@
+//! 密码加密存储
+//!
+//! 连接密码使用 AES-256-GCM 加密后保存，只在建立连接时解密。
+//! 密钥随机生成，保存在系统钥匙串（macOS 钥匙串、Windows 凭据管理器、Linux Secret Service）中，
+//! 不与密文放在同一目录。旧版本保存在配置目录 `vault.key` 中的密钥在启动时移入钥匙串后删除。
+//!
+//! 钥匙串不可用时（如没有 Secret Service 的 Linux）改用配置目录中的 `vault.json`，
+//! 其中的密钥由主密码经 Argon2id 派生的密钥加密。启动时不需要主密码，
+//! 解锁前需要密码的操作返回 `ErrorCode::VaultLocked`，界面输入主密码后调用 `unlock`。
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ErrorCode},
+    DResult,
+};
+
+/// 钥匙串中的服务名和账户名
+const KEYRING_SERVICE: &str = "dbtool";
+const KEYRING_USER: &str = "vault-key";
+/// 旧版本的明文密钥文件
+const LEGACY_KEY_FILE: &str = "vault.key";
+/// 钥匙串不可用时使用的密钥文件
+const VAULT_FILE: &str = "vault.json";
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+lazy_static::lazy_static! {
+    static ref VAULT: Mutex<Vault> = Mutex::new(Vault::Uninit);
+}
+
+enum Vault {
+    Uninit,
+    Unlocked(Key<Aes256Gcm>),
+    /// 使用 `vault.json`，等待输入主密码
+    Locked {
+        dir: PathBuf,
+    },
+}
+
+/// 返回给界面的密钥状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultStatus {
+    Unlocked,
+    /// 需要输入主密码解锁
+    Locked,
+    /// 还没有 `vault.json`，需要设置主密码
+    NoPassphrase,
+}
+
+/// `vault.json` 的内容
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    /// Argon2id 的盐，base64
+    salt: String,
+    /// 派生密钥加密的密钥，base64(nonce || ciphertext)
+    key: String,
+}
+
+/// 从钥匙串加载密钥，没有时迁移旧的密钥文件或生成新密钥。
+/// 钥匙串不可用或已使用 `vault.json` 时保持锁定，由 `unlock` 解锁
+pub fn init(dir: &Path) -> DResult<()> {
+    let key = if dir.join(VAULT_FILE).exists() {
+        None
+    } else {
+        keychain_key(dir)?
+    };
+    *VAULT.lock().unwrap() = match key {
+        Some(key) => Vault::Unlocked(key),
+        None => Vault::Locked {
+            dir: dir.to_path_buf(),
+        },
+    };
+    Ok(())
+}
+
+/// 钥匙串不可用时为 None
+fn keychain_key(dir: &Path) -> DResult<Option<Key<Aes256Gcm>>> {
+    let unavailable = |e: keyring::Error| {
+        log::warn!("keychain is unavailable, using {VAULT_FILE}: {e}");
+        Ok(None)
+    };
+    let entry = match Entry::new(KEYRING_SERVICE, KEYRING_USER) {
+        Ok(entry) => entry,
+        Err(e) => return unavailable(e),
+    };
+    let key = match entry.get_secret() {
+        Ok(bytes) => to_key(&bytes).ok_or_else(|| corrupted_key("keychain"))?,
+        Err(keyring::Error::NoEntry) => {
+            let key = legacy_key(dir)?.unwrap_or_else(|| Aes256Gcm::generate_key(OsRng));
+            if let Err(e) = entry.set_secret(&key) {
+                return unavailable(e);
+            }
+            key
+        }
+        Err(e) => return unavailable(e),
+    };
+    // 密钥已在钥匙串中，删除明文文件
+    remove_legacy_key(dir)?;
+    Ok(Some(key))
+}
+
+fn legacy_key(dir: &Path) -> DResult<Option<Key<Aes256Gcm>>> {
+    let legacy = dir.join(LEGACY_KEY_FILE);
+    if !legacy.exists() {
+        return Ok(None);
+    }
+    let bytes = fs::read(&legacy).map_err(|e| io_error(e, &legacy))?;
+    to_key(&bytes)
+        .map(Some)
+        .ok_or_else(|| corrupted_key(legacy.display()))
+}
+
+fn remove_legacy_key(dir: &Path) -> DResult<()> {
+    let legacy = dir.join(LEGACY_KEY_FILE);
+    if legacy.exists() {
+        fs::remove_file(&legacy).map_err(|e| io_error(e, &legacy))?;
+    }
+    Ok(())
+}
+
+pub fn status() -> VaultStatus {
+    match &*VAULT.lock().unwrap() {
+        Vault::Unlocked(_) => VaultStatus::Unlocked,
+        Vault::Locked { dir } if !dir.join(VAULT_FILE).exists() => VaultStatus::NoPassphrase,
+        Vault::Uninit | Vault::Locked { .. } => VaultStatus::Locked,
+    }
+}
+
+/// 用主密码解锁 `vault.json`。文件不存在时用该密码创建，
+/// 密钥为旧的 `vault.key` 或新生成的密钥
+pub fn unlock(passphrase: &str) -> DResult<()> {
+    let mut vault = VAULT.lock().unwrap();
+    let dir = match &*vault {
+        Vault::Unlocked(_) => return Ok(()),
+        Vault::Uninit => return Err(uninit()),
+        Vault::Locked { dir } => dir.clone(),
+    };
+    if passphrase.is_empty() {
+        return Err(Error::new(
+            ErrorCode::VaultLocked,
+            "master passphrase is empty",
+        ));
+    }
+    let path = dir.join(VAULT_FILE);
+    let key = if path.exists() {
+        let content = fs::read_to_string(&path).map_err(|e| io_error(e, &path))?;
+        let file: VaultFile =
+            serde_json::from_str(&content).map_err(|_| corrupted_key(path.display()))?;
+        open_key(&file, passphrase).map_err(|e| e.with_object(path.display().to_string()))?
+    } else {
+        let key = legacy_key(&dir)?.unwrap_or_else(|| Aes256Gcm::generate_key(OsRng));
+        let content = serde_json::to_string_pretty(&seal_key(&key, passphrase)?)
+            .map_err(|e| Error::new(ErrorCode::Store, e.to_string()))?;
+        fs::write(&path, content).map_err(|e| io_error(e, &path))?;
+        remove_legacy_key(&dir)?;
+        key
+    };
+    *vault = Vault::Unlocked(key);
+    Ok(())
+}
+
+/// 由主密码和盐派生加密密钥的密钥
+fn derive(passphrase: &str, salt: &[u8]) -> DResult<Aes256Gcm> {
+    let mut out = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut out)
+        .map_err(|e| Error::new(ErrorCode::Store, format!("derive vault key failed: {e}")))?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&out)))
+}
+
+fn seal_key(key: &Key<Aes256Gcm>, passphrase: &str) -> DResult<VaultFile> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let mut sealed = nonce.to_vec();
+    sealed.extend(
+        derive(passphrase, &salt)?
+            .encrypt(&nonce, key.as_slice())
+            .map_err(|_| Error::new(ErrorCode::Store, "encrypt vault key failed"))?,
+    );
+    Ok(VaultFile {
+        salt: STANDARD.encode(salt),
+        key: STANDARD.encode(sealed),
+    })
+}
+
+fn open_key(file: &VaultFile, passphrase: &str) -> DResult<Key<Aes256Gcm>> {
+    let salt = STANDARD
+        .decode(&file.salt)
+        .map_err(|_| corrupted_key(VAULT_FILE))?;
+    let sealed = STANDARD
+        .decode(&file.key)
+        .map_err(|_| corrupted_key(VAULT_FILE))?;
+    if sealed.len() < NONCE_LEN {
+        return Err(corrupted_key(VAULT_FILE));
+    }
+    let (nonce, data) = sealed.split_at(NONCE_LEN);
+    let bytes = derive(passphrase, &salt)?
+        .decrypt(Nonce::from_slice(nonce), data)
+        .map_err(|_| Error::new(ErrorCode::VaultLocked, "wrong master passphrase"))?;
+    to_key(&bytes).ok_or_else(|| corrupted_key(VAULT_FILE))
+}
+
+fn to_key(bytes: &[u8]) -> Option<Key<Aes256Gcm>> {
+    (bytes.len() == 32).then(|| *Key::<Aes256Gcm>::from_slice(bytes))
+}
+
+fn corrupted_key(object: impl ToString) -> Error {
+    Error::new(ErrorCode::Store, "vault key is corrupted").with_object(object.to_string())
+}
+
+fn io_error(e: std::io::Error, path: &Path) -> Error {
+    Error::new(ErrorCode::Io, e.to_string()).with_object(path.display().to_string())
+}
+
+fn locked() -> Error {
+    Error::new(
+        ErrorCode::VaultLocked,
+        "vault is locked, enter the master passphrase",
+    )
+}
+
+fn uninit() -> Error {
+    Error::new(ErrorCode::Store, "vault is not initialized")
+}
+
+fn cipher() -> DResult<Aes256Gcm> {
+    match &*VAULT.lock().unwrap() {
+        Vault::Unlocked(key) => Ok(Aes256Gcm::new(key)),
+        Vault::Locked { .. } => Err(locked()),
+        Vault::Uninit => Err(uninit()),
+    }
+}
+
+/// 加密，返回 base64(nonce || ciphertext)
+pub fn encrypt(plain: &str) -> DResult<String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let mut out = nonce.to_vec();
+    out.extend(
+        cipher()?
+            .encrypt(&nonce, plain.as_bytes())
+            .map_err(|_| Error::new(ErrorCode::Store, "encrypt password failed"))?,
+    );
+    Ok(STANDARD.encode(out))
+}
+
+fn corrupted() -> Error {
+    Error::new(ErrorCode::Store, "stored password is corrupted")
+}
+
+/// 解密 `encrypt` 的结果
+pub fn decrypt(secret: &str) -> DResult<String> {
+    let bytes = STANDARD.decode(secret).map_err(|_| corrupted())?;
+    if bytes.len() < NONCE_LEN {
+        return Err(corrupted());
+    }
+    let (nonce, data) = bytes.split_at(NONCE_LEN);
+    let plain = cipher()?
+        .decrypt(Nonce::from_slice(nonce), data)
+        .map_err(|_| Error::new(ErrorCode::Store, "decrypt password failed"))?;
+    String::from_utf8(plain).map_err(|_| corrupted())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passphrase_vault_file() {
+        let key = Aes256Gcm::generate_key(OsRng);
+        let file = seal_key(&key, "correct horse").unwrap();
+        assert_eq!(open_key(&file, "correct horse").unwrap(), key);
+
+        let err = open_key(&file, "wrong").unwrap_err();
+        assert_eq!(err.code, ErrorCode::VaultLocked);
+
+        let other = seal_key(&key, "correct horse").unwrap();
+        assert_ne!(other.salt, file.salt);
+    }
+}